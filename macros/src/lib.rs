@@ -0,0 +1,283 @@
+//! Declaring grammars inline in Rust source.
+//!
+//! ```ignore
+//! mod ab {
+//!     parser_generator_macros::grammar! {
+//!         terminal a = 'a';
+//!         terminal b = 'b';
+//!
+//!         S: (char, char) = a b => { (res1, res2) } | b a => { (res1, res2) };
+//!     }
+//! }
+//!
+//! let result = ab::parse(&mut "ab".bytes().peekable());
+//! ```
+//!
+//! The macro expands to the generated parser. Invalid grammars, including
+//! LL(1) conflicts, are reported as compile errors pointing at the offending
+//! declaration.
+//!
+//! The grammar is written with the syntax of `.grammar` files, but it has to
+//! be made of Rust tokens. Rust does not tokenize a backslash outside of
+//! literals, so escapes like `\d` in regexes or `\p{Letter}` in Unicode
+//! classes are rejected by the compiler before the macro sees them. Such
+//! grammars can be given as a single string literal instead, which the macro
+//! reads like a `.grammar` file:
+//!
+//! ```ignore
+//! mod ident {
+//!     parser_generator_macros::grammar!(r#"
+//!         terminal first = u[\p{Letter}_];
+//!         terminal digits = /\d+/;
+//!
+//!         Ident: (char, Vec<u8>) = first digits => { (res1, res2) };
+//!     "#);
+//! }
+//! ```
+//!
+//! Errors in such a grammar point at the whole literal.
+
+use parser_generator::grammar::{
+    analysis::Analysis,
+    generator::CodeGenerator,
+    loader::{ast::Position, parse_with_source_map},
+    validation::Diagnostic,
+};
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+#[proc_macro]
+pub fn grammar(input: TokenStream) -> TokenStream {
+    let mut source = Source::new();
+    let tokens: Vec<_> = input.clone().into_iter().collect();
+    match tokens.as_slice() {
+        [TokenTree::Literal(literal)] => match string_value(&literal.to_string()) {
+            Some(text) => source.push(&text, literal.span()),
+            None => {
+                return compile_error("expected a grammar or a string literal", literal.span());
+            }
+        },
+        _ => source.push_tokens(input),
+    }
+
+    let (grammar, source_map) = match parse_with_source_map(&source.text) {
+        Ok(result) => result,
+        Err(err) => return compile_error(&err.message, source.span_at(err.pos)),
+    };
+
+    if let Some(diagnostic) = grammar.validate().into_iter().find(|d| d.is_error()) {
+        let span = match &diagnostic {
+            Diagnostic::NoRules { non_terminal } | Diagnostic::Unproductive { non_terminal } => {
+                source.span_at(source_map.symbols[non_terminal])
+            }
+            _ => Span::call_site(),
+        };
+        return compile_error(&diagnostic.to_string(), span);
+    }
+
+    // Report left recursion and conflicts here, so that they can point at the
+    // rules instead of the whole macro invocation.
+    let analysis = Analysis::new(&grammar);
+    let cycles = analysis.left_recursion(&grammar);
+    if !cycles.is_empty() {
+        return cycles
+            .iter()
+            .map(|cycle| {
+                compile_error(
+                    &format!("grammar is left-recursive: {}", cycle),
+                    source.span_at(source_map.rules[cycle.rules[0].index]),
+                )
+            })
+            .collect();
+    }
+
+    let conflicts = analysis.all_conflicts(&grammar);
+    if !conflicts.is_empty() {
+        return conflicts
+            .iter()
+            .map(|conflict| {
+                compile_error(
+                    &format!("grammar is not LL(1): {}", conflict),
+                    source.span_at(source_map.rules[conflict.rules[1].index]),
+                )
+            })
+            .collect();
+    }
+
+    match CodeGenerator::new(grammar).generate() {
+        Ok(file) => file.to_string().parse().unwrap_or_else(|err| {
+            compile_error(
+                &format!("generated invalid code: {}", err),
+                Span::call_site(),
+            )
+        }),
+        Err(err) => compile_error(&err.to_string(), Span::call_site()),
+    }
+}
+
+/// The macro input turned back into grammar source text, remembering where
+/// each token ended up so that positions in the text can be mapped back to
+/// spans.
+///
+/// Tokens are separated like in the macro input, since the grammar syntax is
+/// not whitespace-insensitive everywhere: `[a-z]` is a class of one range,
+/// but `[ a - z ]` is a class of three bytes.
+struct Source {
+    text: String,
+    end: Position,
+    spans: Vec<(Position, Span)>,
+    /// The end of the last token in the macro input.
+    last: Option<Span>,
+}
+
+impl Source {
+    fn new() -> Self {
+        Source {
+            text: String::new(),
+            end: Position::new(1, 1),
+            spans: Vec::new(),
+            last: None,
+        }
+    }
+
+    fn push_tokens(&mut self, tokens: TokenStream) {
+        for token in tokens {
+            match token {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => {
+                            self.push_tokens(group.stream());
+                            continue;
+                        }
+                    };
+                    self.push(open, group.span_open());
+                    self.push_tokens(group.stream());
+                    self.push(close, group.span_close());
+                }
+                TokenTree::Punct(punct) => self.push(&punct.as_char().to_string(), punct.span()),
+                TokenTree::Ident(ident) => self.push(&ident.to_string(), ident.span()),
+                TokenTree::Literal(literal) => self.push(&literal.to_string(), literal.span()),
+            }
+        }
+    }
+
+    fn push(&mut self, text: &str, span: Span) {
+        self.separate(span);
+        self.spans.push((self.end, span));
+        self.write(text);
+        self.last = Some(span.end());
+    }
+
+    /// Writes the whitespace between the last token and the one at `span`.
+    /// Tokens that do not come after the last one in the same file, e.g.
+    /// because they were passed in by another macro, are separated by a
+    /// space, unless they overlap it on the same line.
+    fn separate(&mut self, span: Span) {
+        let Some(last) = self.last else {
+            return;
+        };
+        let start = span.start();
+
+        if start.file() != last.file() {
+            self.write(" ");
+        } else if start.line() > last.line() {
+            self.write(&"\n".repeat(start.line() - last.line()));
+            self.write(&" ".repeat(start.column().saturating_sub(1)));
+        } else if start.line() == last.line() {
+            // The span of the `'` of a lifetime covers the whole lifetime,
+            // so its name starts before the last token ends
+            self.write(&" ".repeat(start.column().saturating_sub(last.column())));
+        } else {
+            self.write(" ");
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        for c in text.chars() {
+            if c == '\n' {
+                self.end.line += 1;
+                self.end.column = 1;
+            } else {
+                self.end.column += 1;
+            }
+        }
+        self.text.push_str(text);
+    }
+
+    /// The span of the last token starting at or before `pos`.
+    fn span_at(&self, pos: Position) -> Span {
+        self.spans
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= pos)
+            .map(|(_, span)| *span)
+            .unwrap_or_else(Span::call_site)
+    }
+}
+
+/// The value of a string literal as written in Rust source, e.g. `"a\tb"` or
+/// `r#"a"b"#`. `None` for other literals.
+fn string_value(literal: &str) -> Option<String> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let quoted = &raw[hashes..raw.len().checked_sub(hashes)?];
+        return quoted
+            .strip_prefix('"')?
+            .strip_suffix('"')
+            .map(str::to_string);
+    }
+
+    let quoted = literal.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::new();
+    let mut chars = quoted.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            '0' => value.push('\0'),
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                value.push(u8::from_str_radix(&hex, 16).ok()? as char);
+            }
+            'u' => {
+                let hex: String = chars
+                    .by_ref()
+                    .skip(1)
+                    .take_while(|c| *c != '}')
+                    .filter(|c| *c != '_')
+                    .collect();
+                value.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            }
+            // A line continuation skips the line break and the indentation
+            '\n' => while chars.next_if(|c| c.is_whitespace()).is_some() {},
+            c => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let mut literal = Literal::string(message);
+    literal.set_span(span);
+
+    let mut group = Group::new(Delimiter::Brace, TokenTree::Literal(literal).into());
+    group.set_span(span);
+
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+
+    [
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(bang),
+        TokenTree::Group(group),
+    ]
+    .into_iter()
+    .collect()
+}
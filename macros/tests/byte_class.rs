@@ -0,0 +1,29 @@
+mod ident {
+    parser_generator_macros::grammar! {
+        terminal letter = [a-zA-Z_];
+
+        Ident: Vec<u8> = letter letter* => { [vec![res1], res2].concat() };
+    }
+}
+
+fn parse(input: &str) -> Result<Vec<u8>, ident::ParseError> {
+    ident::parse(&mut input.bytes().peekable())
+}
+
+#[test]
+fn matches_bytes_in_ranges() {
+    assert_eq!(parse("abc").unwrap(), b"abc");
+    assert_eq!(parse("Snake_Case").unwrap(), b"Snake_Case");
+}
+
+#[test]
+fn rejects_bytes_outside_ranges() {
+    let error = parse("a b").unwrap_err();
+    assert_eq!(error.pos.offset, 1);
+    // The repetition stops at the space, which must then end the input
+    assert_eq!(error.expected, ["end of input"]);
+
+    let error = parse("-").unwrap_err();
+    assert_eq!(error.parsing, "Ident");
+    assert_eq!(error.expected, ["'A'-'Z'", "'_'", "'a'-'z'"]);
+}
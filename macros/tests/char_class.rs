@@ -0,0 +1,48 @@
+mod ident {
+    parser_generator_macros::grammar!(
+        r#"
+        terminal first = u[\p{Letter}_];
+        terminal rest = u[\p{Letter}\p{Number}_];
+
+        Ident: String = first rest* => { [res1].into_iter().chain(res2).collect() };
+    "#
+    );
+}
+
+mod accent {
+    parser_generator_macros::grammar! {
+        terminal vowel = u[aeiouéè];
+
+        Vowels: String = vowel* => { res1.into_iter().collect() };
+    }
+}
+
+fn parse(input: &str) -> Result<String, ident::ParseError> {
+    ident::parse(&mut input.bytes().peekable())
+}
+
+#[test]
+fn matches_categories() {
+    assert_eq!(parse("héllo_2").unwrap(), "héllo_2");
+    assert_eq!(parse("λx").unwrap(), "λx");
+}
+
+#[test]
+fn rejects_characters_outside_class() {
+    let error = parse("2x").unwrap_err();
+    assert_eq!(error.pos.offset, 0);
+    assert_eq!(error.found, ident::Found::Byte(b'2'));
+
+    // 'ê' starts with the same byte as 'é' and 'è', so it is decoded before
+    // being rejected
+    let error = accent::parse(&mut "aê".bytes().peekable()).unwrap_err();
+    assert_eq!(error.pos.offset, 1);
+    assert_eq!(error.found, accent::Found::Char('ê'));
+}
+
+#[test]
+fn matches_characters_in_tokens() {
+    let vowels = accent::parse(&mut "aéè".bytes().peekable()).unwrap();
+    assert_eq!(vowels, "aéè");
+    assert!(accent::parse(&mut "ab".bytes().peekable()).is_err());
+}
@@ -0,0 +1,70 @@
+mod items {
+    parser_generator_macros::grammar! {
+        terminal a = 'a';
+        terminal b = 'b';
+        terminal c = 'c';
+        terminal minus = '-';
+
+        S: (Option<char>, Vec<char>, Vec<(char, char)>) = minus? a+ (b c)* => { (res1, res2, res3) };
+    }
+}
+
+mod groups {
+    parser_generator_macros::grammar! {
+        terminal a = 'a';
+        terminal b = 'b';
+        terminal comma = ',';
+
+        S: Vec<char> = (a | b) (comma (a | b))* => {
+            [vec![res1], res2.into_iter().map(|(_, c)| c).collect()].concat()
+        };
+    }
+}
+
+#[test]
+fn optional_and_repetitions_collect_results() {
+    let parse = |input: &str| items::parse(&mut input.bytes().peekable());
+
+    assert_eq!(parse("a").unwrap(), (None, vec!['a'], vec![]));
+    assert_eq!(
+        parse("-aabcbc").unwrap(),
+        (Some('-'), vec!['a', 'a'], vec![('b', 'c'), ('b', 'c')])
+    );
+}
+
+#[test]
+fn repetition_of_one_requires_an_item() {
+    let error = items::parse(&mut "-b".bytes().peekable()).unwrap_err();
+    assert_eq!(error.pos.offset, 1);
+    assert_eq!(error.expected, ["'a'"]);
+    assert_eq!(error.parsing, "repetition in S");
+
+    let error = items::parse(&mut "abb".bytes().peekable()).unwrap_err();
+    assert_eq!(error.pos.offset, 2);
+    assert_eq!(error.expected, ["'c'"]);
+}
+
+#[test]
+fn group_alternatives_produce_one_result() {
+    let parse = |input: &str| groups::parse(&mut input.bytes().peekable());
+
+    assert_eq!(parse("a,b,a").unwrap(), ['a', 'b', 'a']);
+    assert_eq!(parse("b").unwrap(), ['b']);
+
+    let error = parse("a,").unwrap_err();
+    assert_eq!(error.pos.offset, 2);
+    assert_eq!(error.parsing, "group in S");
+    assert_eq!(
+        error.to_string(),
+        "Error parsing group in S at 1:3: expected one of 'a', 'b' but found end of input"
+    );
+}
+
+#[test]
+fn long_repetitions_do_not_recurse() {
+    let input = "a".repeat(1_000_000) + &"bc".repeat(1_000_000);
+    let (_, a, bc) = items::parse(&mut input.bytes().peekable()).unwrap();
+
+    assert_eq!(a.len(), 1_000_000);
+    assert_eq!(bc.len(), 1_000_000);
+}
@@ -0,0 +1,16 @@
+mod optional {
+    parser_generator_macros::grammar! {
+        terminal a = 'a';
+
+        S: Option<char> = a => { Some(res1) } | => { None };
+    }
+}
+
+#[test]
+fn nullable_start_accepts_end_of_input() {
+    let parse = |input: &str| optional::parse(&mut input.bytes().peekable());
+
+    assert_eq!(parse("").unwrap(), None);
+    assert_eq!(parse("a").unwrap(), Some('a'));
+    assert!(parse("b").is_err());
+}
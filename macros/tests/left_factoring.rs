@@ -0,0 +1,34 @@
+mod expr {
+    parser_generator_macros::grammar! {
+        rewrite left_factoring;
+
+        terminal x = 'x';
+        terminal lparen = '(';
+        terminal rparen = ')';
+        terminal eq = '=';
+
+        S: String = x lparen rparen => { "call()".to_string() }
+            | x lparen S rparen => { format!("call({})", res3) }
+            | x eq S => { format!("assign({})", res3) }
+            | x => { "var".to_string() };
+    }
+}
+
+fn parse(input: &str) -> Result<String, expr::ParseError> {
+    expr::parse(&mut input.bytes().peekable())
+}
+
+#[test]
+fn applies_transforms_of_original_rules() {
+    assert_eq!(parse("x").unwrap(), "var");
+    assert_eq!(parse("x()").unwrap(), "call()");
+    assert_eq!(parse("x(x=x)").unwrap(), "call(assign(var))");
+    assert_eq!(parse("x=x(x())").unwrap(), "assign(call(call()))");
+}
+
+#[test]
+fn reports_errors_after_common_prefix() {
+    let error = parse("x(").unwrap_err();
+    assert_eq!(error.pos.offset, 2);
+    assert_eq!(error.expected, ["')'", "'x'"]);
+}
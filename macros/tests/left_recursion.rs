@@ -0,0 +1,41 @@
+mod arithmetic {
+    parser_generator_macros::grammar! {
+        rewrite left_recursion;
+
+        terminal digit = [0-9];
+        terminal minus = '-';
+
+        E: i64 = E minus T => { res1 - res3 } | T;
+        T: i64 = digit => { (res1 - b'0') as i64 };
+    }
+}
+
+mod spans {
+    parser_generator_macros::grammar! {
+        rewrite left_recursion;
+
+        terminal a = 'a';
+        terminal comma = ',';
+
+        L: Vec<(usize, usize)> = L comma a => {
+            [res1, vec![(span.start.offset, span.end.offset)]].concat()
+        } | a => { vec![(span.start.offset, span.end.offset)] };
+    }
+}
+
+#[test]
+fn folds_from_the_left() {
+    assert_eq!(
+        arithmetic::parse(&mut "9-3-2".bytes().peekable()).unwrap(),
+        4
+    );
+    assert_eq!(arithmetic::parse(&mut "7".bytes().peekable()).unwrap(), 7);
+}
+
+#[test]
+fn keeps_spans_of_original_rules() {
+    assert_eq!(
+        spans::parse(&mut "a,a,a".bytes().peekable()).unwrap(),
+        [(0, 1), (0, 3), (0, 5)]
+    );
+}
@@ -0,0 +1,114 @@
+mod lang {
+    parser_generator_macros::grammar! {
+        lexer;
+        skip [ ];
+
+        terminal kw_let = "let";
+        terminal ident = /[a-z][a-z0-9]*/;
+        terminal eq = '=';
+        terminal eqeq = "==";
+        terminal semi = ';';
+
+        Stmts: Vec<(Vec<u8>, Vec<u8>)> = (kw_let ident eq ident semi)* => {
+            res1.into_iter().map(|(_, name, _, value, _)| (name, value)).collect()
+        };
+    }
+}
+
+mod prefixes {
+    parser_generator_macros::grammar! {
+        lexer;
+
+        terminal kw = "a" | "abc";
+        terminal ident = /[b-z]+/;
+
+        S: Vec<&'static str> = (kw ident?)* => { res1.into_iter().map(|(kw, _)| kw).collect() };
+    }
+}
+
+use lang::TokenKind;
+
+fn kinds(input: &str) -> Vec<TokenKind> {
+    lang::tokenize(input.bytes())
+        .unwrap()
+        .into_iter()
+        .map(|(token, _)| token.kind())
+        .collect()
+}
+
+#[test]
+fn keyword_wins_over_identifier_of_same_length() {
+    assert_eq!(kinds("let"), [TokenKind::KwLet]);
+    assert_eq!(kinds("lets letx le"), [TokenKind::Ident; 3]);
+}
+
+#[test]
+fn longest_token_wins() {
+    assert_eq!(
+        kinds("a==b"),
+        [TokenKind::Ident, TokenKind::Eqeq, TokenKind::Ident]
+    );
+    assert_eq!(
+        kinds("a = = b"),
+        [
+            TokenKind::Ident,
+            TokenKind::Eq,
+            TokenKind::Eq,
+            TokenKind::Ident
+        ]
+    );
+}
+
+#[test]
+fn trie_token_rewinds_to_last_word() {
+    use prefixes::TokenKind;
+
+    let tokens = prefixes::tokenize("abdabc".bytes()).unwrap();
+    let tokens: Vec<_> = tokens
+        .iter()
+        .map(|(token, span)| (token.kind(), span.start.offset, span.end.offset))
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            (TokenKind::Kw, 0, 1),
+            (TokenKind::Ident, 1, 3),
+            (TokenKind::Kw, 3, 6)
+        ]
+    );
+    assert_eq!(
+        prefixes::parse(&mut "abdabc".bytes().peekable()).unwrap(),
+        ["a", "abc"]
+    );
+}
+
+#[test]
+fn tokens_have_spans() {
+    let tokens = lang::tokenize("let x".bytes()).unwrap();
+    let spans: Vec<_> = tokens
+        .iter()
+        .map(|(_, span)| (span.start.offset, span.end.offset))
+        .collect();
+    assert_eq!(spans, [(0, 3), (4, 5)]);
+}
+
+#[test]
+fn parses_tokens() {
+    let parse = |input: &str| lang::parse(&mut input.bytes().peekable());
+
+    assert_eq!(
+        parse("let a = b; let letter = lets;").unwrap(),
+        [
+            (b"a".to_vec(), b"b".to_vec()),
+            (b"letter".to_vec(), b"lets".to_vec())
+        ]
+    );
+
+    let error = parse("let let = a;").unwrap_err();
+    assert_eq!(error.pos.offset, 4);
+    assert_eq!(error.found, lang::Found::Token(TokenKind::KwLet));
+
+    let error = parse("let a = b?").unwrap_err();
+    assert_eq!(error.pos.offset, 9);
+    assert_eq!(error.parsing, "token");
+}
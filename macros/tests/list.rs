@@ -0,0 +1,65 @@
+mod forbid {
+    parser_generator_macros::grammar! {
+        terminal a = 'a';
+        terminal comma = ',';
+        terminal lbracket = '[';
+        terminal rbracket = ']';
+
+        S: Vec<char> = lbracket list(a, comma) rbracket => { res2 };
+    }
+}
+
+mod allow {
+    parser_generator_macros::grammar! {
+        terminal a = 'a';
+        terminal comma = ',';
+        terminal lbracket = '[';
+        terminal rbracket = ']';
+
+        S: Vec<char> = lbracket list(a, comma, trailing = allow) rbracket => { res2 };
+    }
+}
+
+mod require {
+    parser_generator_macros::grammar! {
+        terminal a = 'a';
+        terminal semi = ';';
+
+        S: Vec<char> = list(a, semi, trailing = require);
+    }
+}
+
+#[test]
+fn forbids_trailing_separator_by_default() {
+    let parse = |input: &str| forbid::parse(&mut input.bytes().peekable());
+
+    assert_eq!(parse("[]").unwrap(), []);
+    assert_eq!(parse("[a,a,a]").unwrap(), ['a', 'a', 'a']);
+
+    let error = parse("[a,]").unwrap_err();
+    assert_eq!(error.pos.offset, 3);
+    assert_eq!(error.expected, ["'a'"]);
+}
+
+#[test]
+fn allows_trailing_separator() {
+    let parse = |input: &str| allow::parse(&mut input.bytes().peekable());
+
+    assert_eq!(parse("[]").unwrap(), []);
+    assert_eq!(parse("[a,a]").unwrap(), ['a', 'a']);
+    assert_eq!(parse("[a,a,]").unwrap(), ['a', 'a']);
+    assert!(parse("[,]").is_err());
+    assert!(parse("[a,,]").is_err());
+}
+
+#[test]
+fn requires_trailing_separator() {
+    let parse = |input: &str| require::parse(&mut input.bytes().peekable());
+
+    assert_eq!(parse("").unwrap(), []);
+    assert_eq!(parse("a;a;").unwrap(), ['a', 'a']);
+
+    let error = parse("a;a").unwrap_err();
+    assert_eq!(error.pos.offset, 3);
+    assert_eq!(error.expected, ["';'"]);
+}
@@ -0,0 +1,55 @@
+mod calc {
+    parser_generator_macros::grammar! {
+        terminal digit = [0-9];
+        terminal plus = '+';
+        terminal minus = '-';
+        terminal star = '*';
+        terminal caret = '^';
+        terminal bang = '!';
+        terminal lparen = '(';
+        terminal rparen = ')';
+
+        operators Expr {
+            left 1: plus => { res1 + res3 } | minus => { res1 - res3 };
+            left 2: star => { res1 * res3 };
+            right 3: caret => { res1.pow(res3 as u32) };
+            prefix 4: minus => { -res2 };
+            postfix 5: bang => { (1..=res1).product() };
+        }
+        Expr: i64 = digit => { (res1 - b'0') as i64 } | lparen Expr rparen => { res2 };
+    }
+}
+
+fn eval(input: &str) -> Result<i64, calc::ParseError> {
+    calc::parse(&mut input.bytes().peekable())
+}
+
+#[test]
+fn left_associative_operators_fold_from_the_left() {
+    assert_eq!(eval("9-3-2").unwrap(), 4);
+    assert_eq!(eval("8-2+1").unwrap(), 7);
+}
+
+#[test]
+fn right_associative_operators_fold_from_the_right() {
+    assert_eq!(eval("2^3^2").unwrap(), 512);
+}
+
+#[test]
+fn higher_precedence_binds_tighter() {
+    assert_eq!(eval("1+2*3").unwrap(), 7);
+    assert_eq!(eval("2*3^2").unwrap(), 18);
+    assert_eq!(eval("(1+2)*3").unwrap(), 9);
+    assert_eq!(eval("-2^2").unwrap(), 4);
+    assert_eq!(eval("2*3!").unwrap(), 12);
+    assert_eq!(eval("-3!").unwrap(), -6);
+    assert_eq!(eval("1--2").unwrap(), 3);
+}
+
+#[test]
+fn missing_operand_names_the_expression() {
+    let error = eval("1+").unwrap_err();
+    assert_eq!(error.pos.offset, 2);
+    assert_eq!(error.parsing, "Expr");
+    assert_eq!(error.expected, ["'('", "'-'", "'0'-'9'"]);
+}
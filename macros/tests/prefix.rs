@@ -0,0 +1,74 @@
+mod pair {
+    parser_generator_macros::grammar! {
+        terminal a = 'a';
+        terminal b = 'b';
+
+        S: (char, char) = a b => { (res1, res2) };
+    }
+}
+
+mod repetition {
+    parser_generator_macros::grammar! {
+        terminal a = 'a';
+
+        S: Vec<char> = a*;
+    }
+}
+
+mod statements {
+    parser_generator_macros::grammar! {
+        lexer;
+        skip [ ];
+
+        terminal ident = /[a-z]+/;
+        terminal semi = ';';
+
+        S: Vec<Vec<u8>> = (ident semi)* => {
+            res1.into_iter().map(|(ident, _)| ident).collect()
+        };
+    }
+}
+
+#[test]
+fn leaves_trailing_input() {
+    let mut input = "abc".bytes().peekable();
+
+    assert_eq!(pair::parse_prefix(&mut input).unwrap(), (('a', 'b'), 2));
+    assert_eq!(input.collect::<Vec<_>>(), b"c");
+
+    let error = pair::parse_prefix(&mut "ac".bytes().peekable()).unwrap_err();
+    assert_eq!(error.pos.offset, 1);
+}
+
+#[test]
+fn ends_nullable_tail_at_unexpected_lookahead() {
+    let mut input = "aab".bytes().peekable();
+
+    assert_eq!(
+        repetition::parse_prefix(&mut input).unwrap(),
+        (vec!['a', 'a'], 2)
+    );
+    assert_eq!(input.collect::<Vec<_>>(), b"b");
+
+    let parse_prefix = |input: &str| repetition::parse_prefix(&mut input.bytes().peekable());
+    assert_eq!(parse_prefix("").unwrap(), (vec![], 0));
+    assert_eq!(parse_prefix("b").unwrap(), (vec![], 0));
+    assert!(repetition::parse(&mut "aab".bytes().peekable()).is_err());
+}
+
+#[test]
+fn takes_bytes_with_a_lexer() {
+    let parse_prefix = |input: &str| statements::parse_prefix(&mut input.bytes().peekable());
+
+    // A token that no rule accepts ends the prefix
+    assert_eq!(
+        parse_prefix("a; bc; ; d;").unwrap(),
+        (vec![b"a".to_vec(), b"bc".to_vec()], 6)
+    );
+    // So does a byte that starts no token
+    assert_eq!(
+        parse_prefix("a; bc;?").unwrap(),
+        (vec![b"a".to_vec(), b"bc".to_vec()], 6)
+    );
+    assert!(statements::parse(&mut "a; bc;?".bytes().peekable()).is_err());
+}
@@ -0,0 +1,73 @@
+mod stmts {
+    parser_generator_macros::grammar! {
+        terminal digit = [0-9];
+        terminal plus = '+';
+        terminal semi = ';';
+
+        Stmts: Vec<i64> = (Stmt semi)* => { res1.into_iter().map(|(stmt, _)| stmt).collect() };
+        Stmt: i64 = Num (plus Num)* => { res1 + res2.into_iter().map(|(_, n)| n).sum::<i64>() };
+        Num: i64 = digit => { (res1 - b'0') as i64 };
+
+        recover Stmt sync semi => { -1 };
+    }
+}
+
+mod follow {
+    parser_generator_macros::grammar! {
+        terminal a = 'a';
+        terminal b = 'b';
+        terminal lparen = '(';
+        terminal rparen = ')';
+
+        S: Vec<usize> = (lparen Inner rparen)* => {
+            res1.into_iter().map(|(_, inner, _)| inner).collect()
+        };
+        Inner: usize = a b* => { 1 + res2.len() };
+
+        recover Inner => { 0 };
+    }
+}
+
+fn parse(input: &str) -> (Option<Vec<i64>>, Vec<stmts::ParseError>) {
+    stmts::parse(&mut input.bytes().peekable())
+}
+
+#[test]
+fn parses_without_errors() {
+    let (result, errors) = parse("1+2;3;");
+    assert_eq!(result, Some(vec![3, 3]));
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn skips_to_sync_terminal() {
+    let (result, errors) = parse("1+2;1++2+;4;");
+    assert_eq!(result, Some(vec![3, -1, 4]));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].pos.offset, 6);
+    assert_eq!(errors[0].parsing, "Num");
+}
+
+#[test]
+fn reports_every_error() {
+    let (result, errors) = parse("1+x;1;2+;");
+    assert_eq!(result, Some(vec![-1, 1, -1]));
+    let offsets: Vec<_> = errors.iter().map(|error| error.pos.offset).collect();
+    assert_eq!(offsets, [2, 8]);
+}
+
+#[test]
+fn error_outside_recovering_non_terminal_stops_parsing() {
+    let (result, errors) = parse("1;x;");
+    assert_eq!(result, None);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].pos.offset, 2);
+}
+
+#[test]
+fn skips_to_follow_set_without_sync() {
+    let (result, errors) = follow::parse(&mut "(ab)(ba)(abb)".bytes().peekable());
+    assert_eq!(result, Some(vec![2, 0, 3]));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].pos.offset, 5);
+}
@@ -0,0 +1,68 @@
+mod word {
+    parser_generator_macros::grammar! {
+        terminal word = /[a-z]+/;
+        terminal comma = ',';
+
+        Words: Vec<Vec<u8>> = word (comma word)* => {
+            [vec![res1], res2.into_iter().map(|(_, word)| word).collect()].concat()
+        };
+    }
+}
+
+mod number {
+    parser_generator_macros::grammar!(
+        r#"
+        terminal number = /-?\d+(\.\d+)?/;
+
+        Number: f64 = number => { std::str::from_utf8(&res1).unwrap().parse().unwrap() };
+    "#
+    );
+}
+
+mod fallback {
+    parser_generator_macros::grammar! {
+        lexer;
+
+        terminal ab = /ab(cd)?/;
+        terminal c = 'c';
+
+        S: (Vec<u8>, Option<char>) = ab c? => { (res1, res2) };
+    }
+}
+
+#[test]
+fn matches_regex_in_tokens() {
+    let words = word::parse(&mut "ab,cde".bytes().peekable()).unwrap();
+    assert_eq!(words, [b"ab".to_vec(), b"cde".to_vec()]);
+
+    let error = word::parse(&mut "ab,".bytes().peekable()).unwrap_err();
+    assert_eq!(error.pos.offset, 3);
+    assert_eq!(error.expected, ["/[a-z]+/"]);
+}
+
+#[test]
+fn matches_regex_with_escapes_in_string_literal() {
+    assert_eq!(
+        number::parse(&mut "-12.5".bytes().peekable()).unwrap(),
+        -12.5
+    );
+    assert_eq!(number::parse(&mut "7".bytes().peekable()).unwrap(), 7.0);
+    assert!(number::parse(&mut "x".bytes().peekable()).is_err());
+}
+
+#[test]
+fn lexer_falls_back_to_last_accepting_position() {
+    let tokens = fallback::tokenize("abc".bytes()).unwrap();
+    let kinds: Vec<_> = tokens.iter().map(|(token, _)| token.kind()).collect();
+    assert_eq!(kinds, [fallback::TokenKind::Ab, fallback::TokenKind::C]);
+    assert_eq!(tokens[0].1.end.offset, 2);
+
+    assert_eq!(
+        fallback::parse(&mut "abc".bytes().peekable()).unwrap(),
+        (b"ab".to_vec(), Some('c'))
+    );
+    assert_eq!(
+        fallback::parse(&mut "abcd".bytes().peekable()).unwrap(),
+        (b"abcd".to_vec(), None)
+    );
+}
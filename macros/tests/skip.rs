@@ -0,0 +1,61 @@
+mod nested {
+    parser_generator_macros::grammar! {
+        skip [ ];
+        skip "//";
+        skip "/*" "*/" nested;
+
+        terminal a = 'a';
+        terminal b = 'b';
+
+        S: Vec<char> = (a | b)* => { res1 };
+    }
+}
+
+mod flat {
+    parser_generator_macros::grammar! {
+        lexer;
+        skip "(*" "*)";
+
+        terminal a = 'a';
+        terminal close = ')';
+
+        S: Vec<char> = (a | close)* => { res1 };
+    }
+}
+
+#[test]
+fn skips_bytes_and_comments_between_terminals() {
+    let parse = |input: &str| nested::parse(&mut input.bytes().peekable());
+
+    assert_eq!(parse(" a /* x */b // rest").unwrap(), ['a', 'b']);
+    assert_eq!(parse("// only a comment").unwrap(), []);
+}
+
+#[test]
+fn skips_nested_comments() {
+    let parse = |input: &str| nested::parse(&mut input.bytes().peekable());
+
+    assert_eq!(parse("a/* x /* y */ z */b").unwrap(), ['a', 'b']);
+
+    let error = parse("a /* x /* y */ b").unwrap_err();
+    assert_eq!(error.pos.offset, 16);
+    assert_eq!(error.parsing, "comment");
+    assert_eq!(error.expected, ["\"*/\""]);
+}
+
+#[test]
+fn flat_comments_end_at_first_delimiter() {
+    let tokens: Vec<_> = flat::tokenize("a(* (* *)a)".bytes())
+        .unwrap()
+        .into_iter()
+        .map(|(token, _)| token.kind())
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            flat::TokenKind::A,
+            flat::TokenKind::A,
+            flat::TokenKind::Close
+        ]
+    );
+}
@@ -0,0 +1,59 @@
+mod ops {
+    parser_generator_macros::grammar! {
+        terminal op = "=" | "==" | "===";
+        terminal x = 'x';
+
+        S: Vec<(&'static str, char)> = (op x)* => { res1 };
+    }
+}
+
+mod words {
+    parser_generator_macros::grammar! {
+        terminal word = "foo" | "food" | "foot";
+        terminal s = 's';
+
+        S: (&'static str, Option<char>) = word s? => { (res1, res2) };
+    }
+}
+
+mod prefixes {
+    parser_generator_macros::grammar! {
+        terminal kw = "a" | "abc";
+        terminal rest = /[a-z]+/;
+
+        S: (&'static str, Option<Vec<u8>>) = kw rest? => { (res1, res2) };
+    }
+}
+
+#[test]
+fn matches_longest_word() {
+    let parse = |input: &str| ops::parse(&mut input.bytes().peekable());
+
+    assert_eq!(
+        parse("=x==x===x").unwrap(),
+        [("=", 'x'), ("==", 'x'), ("===", 'x')]
+    );
+}
+
+#[test]
+fn falls_back_to_prefix_word() {
+    let parse = |input: &str| words::parse(&mut input.bytes().peekable());
+
+    assert_eq!(parse("foo").unwrap(), ("foo", None));
+    assert_eq!(parse("foods").unwrap(), ("food", Some('s')));
+    assert_eq!(parse("foos").unwrap(), ("foo", Some('s')));
+
+    let error = parse("fo").unwrap_err();
+    assert_eq!(error.pos.offset, 2);
+    assert_eq!(error.parsing, "word");
+}
+
+#[test]
+fn rewinds_to_last_word_when_longer_word_does_not_complete() {
+    let parse = |input: &str| prefixes::parse(&mut input.bytes().peekable());
+
+    assert_eq!(parse("abc").unwrap(), ("abc", None));
+    assert_eq!(parse("abcd").unwrap(), ("abc", Some(b"d".to_vec())));
+    assert_eq!(parse("abd").unwrap(), ("a", Some(b"bd".to_vec())));
+    assert_eq!(parse("ab").unwrap(), ("a", Some(b"b".to_vec())));
+}
@@ -0,0 +1,47 @@
+mod keywords {
+    parser_generator_macros::grammar! {
+        terminal select = "select" with case_insensitive, not_followed_by = [a-zA-Z0-9_];
+        terminal letter = [a-z];
+        terminal space = ' ';
+
+        S: (&'static str, Vec<u8>) = select space letter+ => { (res1, res3) };
+    }
+}
+
+mod boundary {
+    parser_generator_macros::grammar! {
+        terminal kw = "in" | "int" with not_followed_by = [a-z];
+        terminal semi = ';';
+
+        S: Vec<&'static str> = (kw semi)* => {
+            res1.into_iter().map(|(kw, _)| kw).collect()
+        };
+    }
+}
+
+#[test]
+fn matches_any_case() {
+    let parse = |input: &str| keywords::parse(&mut input.bytes().peekable());
+
+    assert_eq!(parse("select a").unwrap(), ("select", b"a".to_vec()));
+    assert_eq!(parse("SeLeCt ab").unwrap(), ("select", b"ab".to_vec()));
+    assert!(parse("selekt a").is_err());
+}
+
+#[test]
+fn rejects_word_followed_by_class() {
+    let parse = |input: &str| keywords::parse(&mut input.bytes().peekable());
+
+    let error = parse("selection a").unwrap_err();
+    assert_eq!(error.parsing, "select");
+    assert!(parse("select_ a").is_err());
+}
+
+#[test]
+fn boundary_applies_to_longest_word() {
+    let parse = |input: &str| boundary::parse(&mut input.bytes().peekable());
+
+    assert_eq!(parse("in;int;").unwrap(), ["in", "int"]);
+    assert!(parse("inx;").is_err());
+    assert!(parse("intx;").is_err());
+}
@@ -0,0 +1,139 @@
+//! Generating parsers from a build script.
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     parser_generator::build::compile("src/expr.grammar").unwrap();
+//! }
+//!
+//! // src/lib.rs
+//! mod expr {
+//!     parser_generator::include_parser!("expr");
+//! }
+//! ```
+
+use std::{
+    env,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::grammar::{
+    Grammar, error::GrammarError, generator::CodeGenerator, loader::LoadError,
+    validation::Diagnostic,
+};
+
+#[derive(Debug)]
+pub enum BuildError {
+    /// `OUT_DIR` is not set, i.e. we are not running inside a build script.
+    MissingOutDir,
+    Load(LoadError),
+    Generate {
+        path: String,
+        error: GrammarError,
+    },
+    Io {
+        path: String,
+        error: std::io::Error,
+    },
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::MissingOutDir => write!(
+                f,
+                "OUT_DIR is not set, parsers can only be compiled from a build script"
+            ),
+            BuildError::Load(error) => write!(f, "{}", error),
+            BuildError::Generate { path, error } => write!(f, "{}: {}", path, error),
+            BuildError::Io { path, error } => write!(f, "{}: {}", path, error),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Generates a parser for the grammar file at `grammar` into
+/// `$OUT_DIR/<file stem>.rs` and returns the path of the generated file.
+///
+/// Cargo is told to rerun the build script when the grammar changes.
+/// Validation warnings are reported to Cargo as warnings. If the grammar is
+/// invalid, e.g. not LL(1), the error is reported to Cargo as a build error
+/// as well as returned.
+pub fn compile(grammar: impl AsRef<Path>) -> Result<PathBuf, BuildError> {
+    let grammar = grammar.as_ref();
+    println!("cargo::rerun-if-changed={}", grammar.display());
+
+    let result = env::var_os("OUT_DIR")
+        .ok_or(BuildError::MissingOutDir)
+        .and_then(|out_dir| generate(grammar, Path::new(&out_dir)));
+
+    match &result {
+        Ok((_, warnings)) => {
+            for warning in warnings {
+                println!("cargo::warning={}: {}", grammar.display(), warning);
+            }
+        }
+        Err(error) => {
+            for line in error.to_string().lines() {
+                println!("cargo::error={}", line);
+            }
+        }
+    }
+
+    result.map(|(output, _)| output)
+}
+
+/// Like [`compile`], but writes into `out_dir` and does not talk to Cargo.
+pub fn compile_to(
+    grammar: impl AsRef<Path>,
+    out_dir: impl AsRef<Path>,
+) -> Result<PathBuf, BuildError> {
+    generate(grammar.as_ref(), out_dir.as_ref()).map(|(output, _)| output)
+}
+
+/// Returns the path of the generated file and the validation warnings.
+fn generate(grammar: &Path, out_dir: &Path) -> Result<(PathBuf, Vec<Diagnostic>), BuildError> {
+    let display = grammar.display().to_string();
+    let loaded = Grammar::load(grammar).map_err(BuildError::Load)?;
+    let warnings = loaded
+        .validate()
+        .into_iter()
+        .filter(|d| !d.is_error())
+        .collect();
+
+    let file = CodeGenerator::new(loaded)
+        .generate()
+        .map_err(|error| BuildError::Generate {
+            path: display.clone(),
+            error,
+        })?;
+
+    let stem = grammar.file_stem().ok_or_else(|| BuildError::Io {
+        path: display.clone(),
+        error: std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a file"),
+    })?;
+    let output = out_dir.join(stem).with_extension("rs");
+
+    fs::create_dir_all(out_dir).map_err(|error| BuildError::Io {
+        path: out_dir.display().to_string(),
+        error,
+    })?;
+    file.write_to_file(&output)
+        .map_err(|error| BuildError::Io {
+            path: output.display().to_string(),
+            error,
+        })?;
+
+    Ok((output, warnings))
+}
+
+/// Includes a parser generated by [`compile`] from `<name>.grammar`.
+#[macro_export]
+macro_rules! include_parser {
+    ($name:literal) => {
+        include!(concat!(env!("OUT_DIR"), "/", $name, ".rs"));
+    };
+}
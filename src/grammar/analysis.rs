@@ -1,124 +1,695 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet, VecDeque, hash_map::Entry},
+    fmt::Display,
+    iter,
+};
 
-use super::{Grammar, NonTerminalRef, Rule, SymbolRef, TerminalRef};
+use super::{
+    Grammar, NonTerminalRef, StrRepr, SymbolRef, TerminalRef,
+    error::{Conflict, ConflictKind, ConflictRule, LeftRecursion},
+};
 
-pub fn eps(
-    word: &[SymbolRef],
-    grammar: &Grammar,
-    mut visited: &mut HashSet<NonTerminalRef>,
-) -> bool {
-    for symbol in word.iter() {
-        match symbol {
-            SymbolRef::Epsilon => {
-                continue;
-            }
-            SymbolRef::Terminal(_) => {
-                return false;
+/// A token the parser can see when deciding which rule to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Lookahead {
+    Byte(u8),
+    /// A token of the given terminal, in grammars with a
+    /// [lexer](Grammar::lexer).
+    Token(TerminalRef),
+    /// The end of the input.
+    Eof,
+}
+
+impl Display for Lookahead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Lookahead::Byte(byte) => write!(f, "'{}'", byte.escape_ascii()),
+            Lookahead::Token(t_ref) => write!(f, "token #{}", t_ref.index()),
+            Lookahead::Eof => write!(f, "EOF"),
+        }
+    }
+}
+
+impl StrRepr for Lookahead {
+    /// Like the [`Display`] implementation, but names the terminal of a
+    /// token.
+    fn repr(&self, grammar: &Grammar) -> String {
+        match self {
+            Lookahead::Token(t_ref) => t_ref.deref(grammar).name().to_string(),
+            _ => self.to_string(),
+        }
+    }
+}
+
+/// What a terminal can start with: its first bytes, or its own token if the
+/// grammar has a lexer.
+pub fn terminal_first(t_ref: TerminalRef, grammar: &Grammar) -> HashSet<Lookahead> {
+    if grammar.lexer {
+        HashSet::from([Lookahead::Token(t_ref)])
+    } else {
+        t_ref
+            .deref(grammar)
+            .first_bytes()
+            .into_iter()
+            .map(Lookahead::Byte)
+            .collect()
+    }
+}
+
+/// The nullable, FIRST, FOLLOW and PREDICT sets of a grammar.
+///
+/// All sets are computed once, with the usual fixed-point iteration, so
+/// mutually recursive non-terminals get complete sets.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    /// Indexed by non-terminal.
+    nullable: Vec<bool>,
+    /// Indexed by non-terminal. Never contains [`Lookahead::Eof`].
+    first: Vec<HashSet<Lookahead>>,
+    /// Indexed by non-terminal. The FOLLOW set of the start symbol contains
+    /// [`Lookahead::Eof`].
+    follow: Vec<HashSet<Lookahead>>,
+    /// The FIRST set of each rule's RHS, indexed by rule.
+    rule_first: Vec<HashSet<Lookahead>>,
+    /// Indexed by rule.
+    predict: Vec<HashSet<Lookahead>>,
+}
+
+impl Analysis {
+    /// The rules of the grammar must only refer to symbols of the grammar,
+    /// see [`Grammar::validate`].
+    pub fn new(grammar: &Grammar) -> Self {
+        let count = grammar.non_terminals.len();
+        let mut analysis = Analysis {
+            nullable: vec![false; count],
+            first: vec![HashSet::new(); count],
+            follow: vec![HashSet::new(); count],
+            rule_first: Vec::new(),
+            predict: Vec::new(),
+        };
+
+        analysis.compute_nullable(grammar);
+        analysis.compute_first(grammar);
+        analysis.compute_follow(grammar);
+
+        for rule in &grammar.rules {
+            let first = analysis.word_first(rule.rhs.as_slice(), grammar);
+            let mut predict = first.clone();
+            if analysis.is_word_nullable(rule.rhs.as_slice()) {
+                predict.extend(analysis.follow(rule.lhs));
             }
-            SymbolRef::NonTerminal(nt_ref) => {
-                if visited.contains(nt_ref) {
-                    return false;
-                }
-                visited.insert(nt_ref.clone());
 
-                let nt = symbol.unwrap_as_non_terminal(grammar);
+            analysis.rule_first.push(first);
+            analysis.predict.push(predict);
+        }
 
-                if !grammar
-                    .get_rules_for_non_terminal(nt)
-                    .iter()
-                    .any(|rule| eps(rule.rhs.as_slice(), grammar, &mut visited))
-                {
-                    return false;
+        analysis
+    }
+
+    fn compute_nullable(&mut self, grammar: &Grammar) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in &grammar.rules {
+                if !self.nullable[rule.lhs.index()] && self.is_word_nullable(rule.rhs.as_slice()) {
+                    self.nullable[rule.lhs.index()] = true;
+                    changed = true;
                 }
             }
         }
     }
 
-    true
-}
+    fn compute_first(&mut self, grammar: &Grammar) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in &grammar.rules {
+                let first = self.word_first(rule.rhs.as_slice(), grammar);
+                let set = &mut self.first[rule.lhs.index()];
+                let len = set.len();
+                set.extend(first);
+                changed |= set.len() != len;
+            }
+        }
+    }
 
-pub fn first(
-    word: &[SymbolRef],
-    grammar: &Grammar,
-    mut visited: &mut HashSet<NonTerminalRef>,
-) -> HashSet<u8> {
-    let mut first_set = HashSet::new();
+    fn compute_follow(&mut self, grammar: &Grammar) {
+        if let Some(start) = grammar.start {
+            self.follow[start.index()].insert(Lookahead::Eof);
+        }
 
-    for symbol in word.iter() {
-        match symbol {
-            SymbolRef::Epsilon => {
-                continue;
-            }
-            SymbolRef::Terminal(t) => {
-                first_set.extend(t.deref(grammar).first_bytes());
-                break;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in &grammar.rules {
+                let rhs = rule.rhs.as_slice();
+                for (i, symbol) in rhs.iter().enumerate() {
+                    let SymbolRef::NonTerminal(nt_ref) = symbol else {
+                        continue;
+                    };
+
+                    let rest = &rhs[i + 1..];
+                    let mut follow = self.word_first(rest, grammar);
+                    if self.is_word_nullable(rest) {
+                        follow.extend(&self.follow[rule.lhs.index()]);
+                    }
+
+                    let set = &mut self.follow[nt_ref.index()];
+                    let len = set.len();
+                    set.extend(follow);
+                    changed |= set.len() != len;
+                }
             }
-            SymbolRef::NonTerminal(nt_ref) => {
-                if visited.contains(nt_ref) {
+        }
+    }
+
+    pub fn is_nullable(&self, nt: NonTerminalRef) -> bool {
+        self.nullable[nt.index()]
+    }
+
+    pub fn first(&self, nt: NonTerminalRef) -> &HashSet<Lookahead> {
+        &self.first[nt.index()]
+    }
+
+    pub fn follow(&self, nt: NonTerminalRef) -> &HashSet<Lookahead> {
+        &self.follow[nt.index()]
+    }
+
+    /// The FIRST set of the RHS of the rule with the given index.
+    pub fn rule_first(&self, rule: usize) -> &HashSet<Lookahead> {
+        &self.rule_first[rule]
+    }
+
+    /// Predict is the set of lookaheads for which the parser chooses the
+    /// rule. It contains at least the FIRST set of the rule's RHS. If the
+    /// rule's RHS can derive epsilon, it also contains the FOLLOW set of the
+    /// rule's LHS, which may include [`Lookahead::Eof`].
+    pub fn predict(&self, rule: usize) -> &HashSet<Lookahead> {
+        &self.predict[rule]
+    }
+
+    /// Whether the word can derive epsilon.
+    pub fn is_word_nullable(&self, word: &[SymbolRef]) -> bool {
+        word.iter().all(|symbol| match symbol {
+            SymbolRef::Epsilon => true,
+            SymbolRef::Terminal(_) => false,
+            SymbolRef::NonTerminal(nt_ref) => self.nullable[nt_ref.index()],
+        })
+    }
+
+    /// The bytes or tokens that can start a string derived from the word.
+    pub fn word_first(&self, word: &[SymbolRef], grammar: &Grammar) -> HashSet<Lookahead> {
+        let mut first = HashSet::new();
+
+        for symbol in word {
+            match symbol {
+                SymbolRef::Epsilon => {}
+                SymbolRef::Terminal(t_ref) => {
+                    first.extend(terminal_first(*t_ref, grammar));
                     break;
                 }
-                visited.insert(nt_ref.clone());
+                SymbolRef::NonTerminal(nt_ref) => {
+                    first.extend(&self.first[nt_ref.index()]);
+                    if !self.nullable[nt_ref.index()] {
+                        break;
+                    }
+                }
+            }
+        }
+
+        first
+    }
+
+    /// Finds every pair of rules of `nt` whose predict sets overlap.
+    pub fn conflicts(&self, nt: NonTerminalRef, grammar: &Grammar) -> Vec<Conflict> {
+        self.conflicts_with_prefixes(nt, grammar, &shortest_prefixes(grammar))
+    }
+
+    /// Finds every conflict in the grammar, see [`Analysis::conflicts`].
+    pub fn all_conflicts(&self, grammar: &Grammar) -> Vec<Conflict> {
+        let prefixes = shortest_prefixes(grammar);
+
+        grammar
+            .iter_non_terminal_refs()
+            .flat_map(|nt| self.conflicts_with_prefixes(nt, grammar, &prefixes))
+            .collect()
+    }
 
-                let nt = nt_ref.deref(grammar);
-                for rule in grammar.get_rules_for_non_terminal(nt) {
-                    first_set.extend(first(rule.rhs.as_slice(), grammar, &mut visited));
+    /// Finds the non-terminals that can start with themselves, directly or
+    /// through other non-terminals, possibly after nullable symbols. For each
+    /// of them the shortest such cycle is reported, unless the same cycle was
+    /// already reported for an earlier non-terminal.
+    pub fn left_recursion(&self, grammar: &Grammar) -> Vec<LeftRecursion> {
+        // An edge leads from a non-terminal to each non-terminal one of its
+        // rules can start with
+        let mut edges = vec![Vec::new(); grammar.non_terminals.len()];
+        for (i, rule) in grammar.rules.iter().enumerate() {
+            for symbol in rule.rhs.iter() {
+                match symbol {
+                    SymbolRef::Epsilon => {}
+                    SymbolRef::Terminal(_) => break,
+                    SymbolRef::NonTerminal(nt_ref) => {
+                        edges[rule.lhs.index()].push((*nt_ref, i));
+                        if !self.is_nullable(*nt_ref) {
+                            break;
+                        }
+                    }
                 }
+            }
+        }
 
-                if !grammar
-                    .get_rules_for_non_terminal(nt)
+        let mut seen = HashSet::new();
+        let mut cycles = Vec::new();
+
+        for start in grammar.iter_non_terminal_refs() {
+            let Some(path) = shortest_cycle(start, &edges) else {
+                continue;
+            };
+
+            let mut key: Vec<_> = path.iter().map(|(_, rule)| *rule).collect();
+            key.sort();
+            if !seen.insert(key) {
+                continue;
+            }
+
+            cycles.push(LeftRecursion {
+                cycle: iter::once(start)
+                    .chain(path.iter().map(|(nt_ref, _)| *nt_ref))
+                    .map(|nt_ref| nt_ref.deref(grammar).name.clone())
+                    .collect(),
+                rules: path
                     .iter()
-                    .any(|rule| eps(rule.rhs.as_slice(), grammar, &mut HashSet::new()))
-                {
-                    break;
+                    .map(|(_, rule)| ConflictRule {
+                        index: *rule,
+                        repr: grammar.rules[*rule].repr(grammar),
+                    })
+                    .collect(),
+            });
+        }
+
+        cycles
+    }
+
+    fn conflicts_with_prefixes(
+        &self,
+        nt: NonTerminalRef,
+        grammar: &Grammar,
+        prefixes: &HashMap<NonTerminalRef, Vec<TerminalRef>>,
+    ) -> Vec<Conflict> {
+        let rules: Vec<_> = grammar.iter_rules_for_non_terminal(nt).collect();
+        let mut conflicts = Vec::new();
+
+        for (a, (i, rule_i)) in rules.iter().enumerate() {
+            for (j, rule_j) in &rules[a + 1..] {
+                let mut lookahead: Vec<_> = self
+                    .predict(*i)
+                    .intersection(self.predict(*j))
+                    .copied()
+                    .collect();
+                if lookahead.is_empty() {
+                    continue;
                 }
+                lookahead.sort();
+
+                let kind = if lookahead.iter().any(|lookahead| {
+                    self.rule_first(*i).contains(lookahead)
+                        && self.rule_first(*j).contains(lookahead)
+                }) {
+                    ConflictKind::FirstFirst
+                } else {
+                    ConflictKind::FirstFollow
+                };
+
+                conflicts.push(Conflict {
+                    non_terminal: nt.deref(grammar).name.clone(),
+                    kind,
+                    rules: [
+                        ConflictRule {
+                            index: *i,
+                            repr: rule_i.repr(grammar),
+                        },
+                        ConflictRule {
+                            index: *j,
+                            repr: rule_j.repr(grammar),
+                        },
+                    ],
+                    witness: prefixes.get(&nt).map(|prefix| match lookahead[0] {
+                        Lookahead::Byte(b) => {
+                            let mut witness = witness_input(prefix, grammar);
+                            witness.push(b);
+                            witness
+                        }
+                        Lookahead::Token(t_ref) => {
+                            witness_input(&[prefix.as_slice(), &[t_ref]].concat(), grammar)
+                        }
+                        Lookahead::Eof => witness_input(prefix, grammar),
+                    }),
+                    lookahead_repr: lookahead
+                        .iter()
+                        .map(|lookahead| lookahead.repr(grammar))
+                        .collect(),
+                    lookahead,
+                });
             }
         }
+
+        conflicts
+    }
+}
+
+/// The shortest path along `edges` from `start` back to itself, as the
+/// non-terminal reached by each step and the rule it was reached by.
+fn shortest_cycle(
+    start: NonTerminalRef,
+    edges: &[Vec<(NonTerminalRef, usize)>],
+) -> Option<Vec<(NonTerminalRef, usize)>> {
+    let mut parents: HashMap<NonTerminalRef, (NonTerminalRef, usize)> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(nt_ref) = queue.pop_front() {
+        for &(next, rule) in &edges[nt_ref.index()] {
+            if next == start {
+                let mut path = vec![(start, rule)];
+                let mut current = nt_ref;
+                while current != start {
+                    let (parent, rule) = parents[&current];
+                    path.push((current, rule));
+                    current = parent;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            if let Entry::Vacant(entry) = parents.entry(next) {
+                entry.insert((nt_ref, rule));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// The input made of the shortest matches of the terminals. With a
+/// [lexer](Grammar::lexer), the matches are separated by skipped input, so
+/// that e.g. a keyword followed by an identifier is not tokenized as a
+/// single identifier. Without a skip set they can only be concatenated.
+fn witness_input(terminals: &[TerminalRef], grammar: &Grammar) -> Vec<u8> {
+    let separator = if grammar.lexer {
+        token_separator(grammar)
+    } else {
+        Vec::new()
+    };
+
+    let matches: Vec<_> = terminals
+        .iter()
+        .map(|t_ref| t_ref.deref(grammar).shortest_match())
+        .collect();
+    matches.join(separator.as_slice())
+}
+
+/// The shortest skipped input, preferring a space over other bytes and
+/// bytes over an empty comment.
+fn token_separator(grammar: &Grammar) -> Vec<u8> {
+    let bytes = grammar.skip.bytes();
+    if bytes.contains(&b' ') {
+        return vec![b' '];
+    }
+    if let Some(byte) = bytes.iter().min() {
+        return vec![*byte];
     }
 
-    first_set
+    grammar
+        .skip
+        .block_comments()
+        .iter()
+        .map(|comment| [comment.start.as_slice(), &comment.end].concat())
+        .chain(
+            grammar
+                .skip
+                .line_comments()
+                .iter()
+                .map(|start| [start.as_slice(), b"\n"].concat()),
+        )
+        .min_by_key(Vec::len)
+        .unwrap_or_default()
 }
 
-pub fn follow(
-    nt: &NonTerminalRef,
+/// The terminals of the shortest input each productive non-terminal derives,
+/// given the length of the shortest match of each terminal.
+fn shortest_yields(
     grammar: &Grammar,
-    mut visited: &mut HashSet<NonTerminalRef>,
-) -> HashSet<u8> {
-    let mut follow_set = HashSet::new();
+    match_lens: &[usize],
+) -> HashMap<NonTerminalRef, Vec<TerminalRef>> {
+    let mut yields: HashMap<NonTerminalRef, Vec<TerminalRef>> = HashMap::new();
 
-    for rule in &grammar.rules {
-        if !rule.rhs.iter().any(|s| s.eq_non_terminal(nt)) {
-            continue;
-        }
+    loop {
+        let mut changed = false;
 
-        for (i, symbol) in rule.rhs.iter().enumerate() {
-            if !symbol.eq_non_terminal(nt) {
-                continue;
+        for rule in &grammar.rules {
+            let candidate = rule
+                .rhs
+                .iter()
+                .map(|symbol| symbol_yield(symbol, &yields))
+                .collect::<Option<Vec<_>>>()
+                .map(|parts| parts.concat());
+
+            if let Some(candidate) = candidate
+                && yields.get(&rule.lhs).is_none_or(|current| {
+                    yield_len(&candidate, match_lens) < yield_len(current, match_lens)
+                })
+            {
+                yields.insert(rule.lhs, candidate);
+                changed = true;
             }
+        }
 
-            let rest = &rule.rhs.0[i + 1..];
-            follow_set.extend(first(rest, grammar, &mut HashSet::new()));
+        if !changed {
+            return yields;
+        }
+    }
+}
 
-            if !visited.contains(&rule.lhs) && eps(rest, grammar, &mut HashSet::new()) {
-                visited.insert(rule.lhs.clone());
+fn symbol_yield(
+    symbol: &SymbolRef,
+    yields: &HashMap<NonTerminalRef, Vec<TerminalRef>>,
+) -> Option<Vec<TerminalRef>> {
+    match symbol {
+        SymbolRef::Epsilon => Some(Vec::new()),
+        SymbolRef::Terminal(t_ref) => Some(vec![*t_ref]),
+        SymbolRef::NonTerminal(nt_ref) => yields.get(nt_ref).cloned(),
+    }
+}
+
+fn yield_len(terminals: &[TerminalRef], match_lens: &[usize]) -> usize {
+    terminals
+        .iter()
+        .map(|t_ref| match_lens[t_ref.index()])
+        .sum()
+}
+
+/// The terminals of the shortest input after which the parser starts parsing
+/// each reachable non-terminal.
+fn shortest_prefixes(grammar: &Grammar) -> HashMap<NonTerminalRef, Vec<TerminalRef>> {
+    let Some(start) = grammar.start else {
+        return HashMap::new();
+    };
+
+    let match_lens: Vec<_> = grammar
+        .terminals
+        .iter()
+        .map(|t| t.shortest_match().len())
+        .collect();
+    let yields = shortest_yields(grammar, &match_lens);
+    let mut prefixes = HashMap::from([(start, Vec::new())]);
+
+    loop {
+        let mut changed = false;
+
+        for rule in &grammar.rules {
+            let Some(mut prefix) = prefixes.get(&rule.lhs).cloned() else {
+                continue;
+            };
 
-                follow_set.extend(follow(&rule.lhs, grammar, &mut visited));
+            for symbol in rule.rhs.iter() {
+                if let SymbolRef::NonTerminal(nt_ref) = symbol
+                    && prefixes.get(nt_ref).is_none_or(|current| {
+                        yield_len(&prefix, &match_lens) < yield_len(current, &match_lens)
+                    })
+                {
+                    prefixes.insert(*nt_ref, prefix.clone());
+                    changed = true;
+                }
+
+                match symbol_yield(symbol, &yields) {
+                    Some(part) => prefix.extend(part),
+                    None => break,
+                }
             }
         }
-    }
 
-    follow_set
+        if !changed {
+            return prefixes;
+        }
+    }
 }
 
-/// Predict is the set of terminals that can appear at the beginning of a string
-/// derived from the rule. It contains at least the FIRST set of the rule's RHS.
-/// If the rule's RHS can derive epsilon, it also contains the FOLLOW set of the
-/// rule's LHS.
-pub fn predict(rule: &Rule, grammar: &Grammar) -> HashSet<u8> {
-    let mut predict = first(rule.rhs.as_slice(), grammar, &mut HashSet::new());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grammar::error::ConflictKind;
+
+    fn conflicts(source: &str) -> Vec<Conflict> {
+        let grammar: Grammar = source.parse().unwrap();
+        Analysis::new(&grammar).all_conflicts(&grammar)
+    }
+
+    #[test]
+    fn first_first_conflict_has_witness() {
+        let conflicts = conflicts(
+            "terminal a = 'a'; terminal b = 'b';
+             S = b T => { () };
+             T = a a => { () } | a;",
+        );
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].non_terminal, "T");
+        assert_eq!(conflicts[0].kind, ConflictKind::FirstFirst);
+        assert_eq!(conflicts[0].lookahead_repr, ["'a'"]);
+        assert_eq!(conflicts[0].witness.as_deref(), Some(&b"ba"[..]));
+    }
+
+    #[test]
+    fn first_follow_conflict_has_witness() {
+        let conflicts = conflicts(
+            "terminal a = 'a';
+             S = T a => { () };
+             T = a | ε;",
+        );
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, ConflictKind::FirstFollow);
+        assert_eq!(conflicts[0].witness.as_deref(), Some(&b"a"[..]));
+    }
+
+    #[test]
+    fn witness_separates_tokens_with_skipped_input() {
+        let conflicts = conflicts(
+            "lexer;
+             skip [\\t ];
+             terminal kw = \"let\";
+             terminal ident = /[a-z]+/;
+             terminal eq = '=';
+             S = kw A => { () };
+             A = ident eq => { () } | ident => { () };",
+        );
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].witness.as_deref(), Some(&b"let a"[..]));
+    }
+
+    #[test]
+    fn witness_separates_tokens_with_comment_without_skipped_bytes() {
+        let conflicts = conflicts(
+            "lexer;
+             skip \"/*\" \"*/\";
+             terminal kw = \"let\";
+             terminal ident = /[a-z]+/;
+             S = kw A => { () };
+             A = ident ident => { () } | ident => { () };",
+        );
 
-    if eps(rule.rhs.as_slice(), grammar, &mut HashSet::new()) {
-        predict.extend(follow(&rule.lhs, grammar, &mut HashSet::new()));
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].witness.as_deref(), Some(&b"let/**/a"[..]));
     }
 
-    predict
+    #[test]
+    fn unreachable_conflict_has_no_witness() {
+        let conflicts = conflicts(
+            "terminal a = 'a';
+             S = a;
+             T = a a => { () } | a;",
+        );
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].witness, None);
+    }
+
+    #[test]
+    fn mutual_recursion_reaches_fixed_point() {
+        let grammar: Grammar = "terminal x = 'x'; terminal y = 'y'; terminal z = 'z';
+             A = B x => { () } | ε;
+             B = A y => { () } | z => { () };"
+            .parse()
+            .unwrap();
+        let analysis = Analysis::new(&grammar);
+        let (a, b) = (NonTerminalRef::new(0), NonTerminalRef::new(1));
+
+        assert!(analysis.is_nullable(a));
+        assert!(!analysis.is_nullable(b));
+        let first = HashSet::from([Lookahead::Byte(b'y'), Lookahead::Byte(b'z')]);
+        assert_eq!(analysis.first(a), &first);
+        assert_eq!(analysis.first(b), &first);
+        assert_eq!(
+            analysis.follow(a),
+            &HashSet::from([Lookahead::Byte(b'y'), Lookahead::Eof])
+        );
+        assert_eq!(analysis.follow(b), &HashSet::from([Lookahead::Byte(b'x')]));
+    }
+
+    fn left_recursion(source: &str) -> Vec<LeftRecursion> {
+        let grammar: Grammar = source.parse().unwrap();
+        Analysis::new(&grammar).left_recursion(&grammar)
+    }
+
+    #[test]
+    fn direct_left_recursion_is_a_cycle_of_one_rule() {
+        let cycles = left_recursion(
+            "terminal a = 'a'; terminal b = 'b';
+             S = S a => { () } | b;",
+        );
+
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].cycle, ["S", "S"]);
+        assert_eq!(cycles[0].rules.len(), 1);
+        assert_eq!(cycles[0].rules[0].index, 0);
+    }
+
+    #[test]
+    fn indirect_left_recursion_is_reported_once() {
+        let cycles = left_recursion(
+            "terminal a = 'a'; terminal b = 'b'; terminal c = 'c';
+             S = A;
+             A = B a => { () };
+             B = A b => { () } | c;",
+        );
+
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].cycle, ["A", "B", "A"]);
+        let rules: Vec<_> = cycles[0].rules.iter().map(|rule| rule.index).collect();
+        assert_eq!(rules, [1, 2]);
+    }
+
+    #[test]
+    fn left_recursion_behind_nullable_symbol_is_found() {
+        let cycles = left_recursion(
+            "terminal a = 'a';
+             S = N S a => { () } | a;
+             N = ε;",
+        );
+
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].cycle, ["S", "S"]);
+    }
+
+    #[test]
+    fn right_recursion_is_not_left_recursion() {
+        assert!(
+            left_recursion(
+                "terminal a = 'a';
+                 S = a S => { () } | ε;",
+            )
+            .is_empty()
+        );
+    }
 }
@@ -0,0 +1,347 @@
+//! EBNF operators on the right hand side of rules. They are desugared into
+//! helper non-terminals when the rule is added, so the analysis and the
+//! generator only ever see plain rules.
+
+use super::{
+    Grammar, Rule, Word,
+    error::GrammarError,
+    rewrite::word,
+    symbols::{
+        non_terminal::{NonTerminalKind, Trailing},
+        refs::{NonTerminalRef, SymbolRef},
+    },
+};
+
+/// A part of the right hand side of a rule added with
+/// [`Grammar::add_ebnf_rule`]. Each element produces one result.
+#[derive(Debug, Clone)]
+pub enum Element {
+    Symbol(SymbolRef),
+    /// `e?`, evaluates to an `Option` of the result of `e`.
+    Optional(Box<Element>),
+    /// `e*`, evaluates to a `Vec` of the results of `e`.
+    Repeat(Box<Element>),
+    /// `e+`, like [`Element::Repeat`], but matches `e` at least once.
+    RepeatOne(Box<Element>),
+    /// `(a b | c)`, evaluates to the result of the chosen alternative: the
+    /// result of its only element, a tuple of the results of its elements,
+    /// or `()` if it is empty. All alternatives must have the same type.
+    Group(Vec<Vec<Element>>),
+    /// `list(item, separator, trailing = ...)`, evaluates to a `Vec` of the
+    /// results of `item`. The list may be empty.
+    List {
+        item: Box<Element>,
+        separator: Box<Element>,
+        trailing: Trailing,
+    },
+}
+
+impl Element {
+    pub fn test_indices(&self, grammar: &Grammar) -> Result<(), GrammarError> {
+        match self {
+            Element::Symbol(symbol) => symbol.test_index(grammar),
+            Element::Optional(inner) | Element::Repeat(inner) | Element::RepeatOne(inner) => {
+                inner.test_indices(grammar)
+            }
+            Element::Group(alternatives) => alternatives
+                .iter()
+                .flatten()
+                .try_for_each(|element| element.test_indices(grammar)),
+            Element::List {
+                item, separator, ..
+            } => {
+                item.test_indices(grammar)?;
+                separator.test_indices(grammar)
+            }
+        }
+    }
+}
+
+impl Grammar {
+    /// Adds a rule whose right hand side may use EBNF operators. Each
+    /// operator becomes a helper non-terminal of `lhs`, and its result is
+    /// available to `transform` as the `resN` of the element, like that of a
+    /// plain symbol.
+    pub fn add_ebnf_rule(
+        &mut self,
+        lhs: NonTerminalRef,
+        elements: Vec<Element>,
+        transform: String,
+    ) -> Result<(), GrammarError> {
+        lhs.test_index(self)?;
+        for element in &elements {
+            element.test_indices(self)?;
+        }
+
+        let symbols = elements
+            .iter()
+            .map(|element| self.desugar(lhs, element))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.add_rule(Rule::new(lhs, word(symbols), transform))
+    }
+
+    /// Turns the element into a single symbol, adding helper non-terminals
+    /// for its operators.
+    fn desugar(
+        &mut self,
+        lhs: NonTerminalRef,
+        element: &Element,
+    ) -> Result<SymbolRef, GrammarError> {
+        let helper = match element {
+            Element::Symbol(symbol) => return Ok(*symbol),
+            Element::Optional(inner) => {
+                let item = self.desugar(lhs, inner)?;
+                let ast_type = format!("Option<{}>", self.result_type(item));
+                let helper = self.add_part(lhs, "option", ast_type);
+
+                self.add_rule(Rule::new(
+                    helper,
+                    Word::new(vec![item]),
+                    "Some(res1)".to_string(),
+                ))?;
+                self.add_rule(Rule::new(
+                    helper,
+                    Word::new(vec![SymbolRef::Epsilon]),
+                    "None".to_string(),
+                ))?;
+                helper
+            }
+            Element::Repeat(inner) => {
+                let item = self.desugar(lhs, inner)?;
+                self.add_repeat(lhs, item)?
+            }
+            Element::RepeatOne(inner) => {
+                let item = self.desugar(lhs, inner)?;
+                let items = self.add_repeat(lhs, item)?;
+                let ast_type = format!("Vec<{}>", self.result_type(item));
+                let helper = self.add_part(lhs, "repetition", ast_type);
+
+                self.add_rule(Rule::new(
+                    helper,
+                    Word::new(vec![item, SymbolRef::NonTerminal(items)]),
+                    "{ let mut __items = res2; __items.insert(0, res1); __items }".to_string(),
+                ))?;
+                helper
+            }
+            Element::Group(alternatives) => {
+                let alternatives = alternatives
+                    .iter()
+                    .map(|elements| {
+                        elements
+                            .iter()
+                            .map(|element| self.desugar(lhs, element))
+                            .collect::<Result<Vec<_>, _>>()
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                // Every alternative is a rule of the same helper, so their
+                // results must have the same type
+                let mut types = alternatives
+                    .iter()
+                    .map(|symbols| self.group_result_type(symbols));
+                let ast_type = types.next().unwrap_or_else(|| "()".to_string());
+                if let Some(other) = types.find(|other| !same_type(other, &ast_type)) {
+                    return Err(GrammarError::GroupTypeMismatch {
+                        non_terminal: lhs.deref(self).name.clone(),
+                        first: ast_type,
+                        other,
+                    });
+                }
+                let helper = self.add_part(lhs, "group", ast_type);
+
+                for symbols in alternatives {
+                    let transform = match symbols.len() {
+                        0 => "()".to_string(),
+                        1 => "res1".to_string(),
+                        n => format!(
+                            "({})",
+                            (1..=n)
+                                .map(|i| format!("res{}", i))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    };
+                    self.add_rule(Rule::new(helper, word(symbols), transform))?;
+                }
+                helper
+            }
+            Element::List {
+                item,
+                separator,
+                trailing,
+            } => {
+                let item = self.desugar(lhs, item)?;
+                let separator = self.desugar(lhs, separator)?;
+                self.add_list(lhs, item, separator, *trailing)?
+            }
+        };
+
+        Ok(SymbolRef::NonTerminal(helper))
+    }
+
+    /// Adds a helper non-terminal for a part of the rules of `lhs`, like a
+    /// group, whose errors say they are in that part of `lhs`.
+    fn add_part(&mut self, lhs: NonTerminalRef, part: &str, ast_type: String) -> NonTerminalRef {
+        let helper = self.add_helper_non_terminal(lhs, ast_type);
+        self.non_terminals[helper.index()].description =
+            Some(format!("{} in {}", part, lhs.deref(self).name));
+        helper
+    }
+
+    /// The result type of a group alternative: the result type of its only
+    /// symbol, a tuple of the result types of its symbols, or `()`.
+    fn group_result_type(&self, symbols: &[SymbolRef]) -> String {
+        match symbols {
+            [] => "()".to_string(),
+            [symbol] => self.result_type(*symbol),
+            symbols => format!(
+                "({})",
+                symbols
+                    .iter()
+                    .map(|symbol| self.result_type(*symbol))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Adds a list non-terminal. The generated parser parses it with a loop,
+    /// its rules only describe it to the analysis. They are:
+    ///
+    /// - `L -> item L' | ε` and `L' -> separator item L' | ε` without a
+    ///   trailing separator,
+    /// - `L -> item L' | ε` and `L' -> separator L | ε` if a trailing separator
+    ///   is allowed,
+    /// - `L -> item separator L | ε` if it is required.
+    fn add_list(
+        &mut self,
+        lhs: NonTerminalRef,
+        item: SymbolRef,
+        separator: SymbolRef,
+        trailing: Trailing,
+    ) -> Result<NonTerminalRef, GrammarError> {
+        let ast_type = format!("Vec<{}>", self.result_type(item));
+        let list = self.add_part(lhs, "list", ast_type.clone());
+        self.non_terminals[list.index()].kind = NonTerminalKind::List {
+            item,
+            separator,
+            trailing,
+        };
+
+        if trailing == Trailing::Require {
+            self.add_rule(Rule::new(
+                list,
+                Word::new(vec![item, separator, SymbolRef::NonTerminal(list)]),
+                "{ let mut __items = res3; __items.insert(0, res1); __items }".to_string(),
+            ))?;
+        } else {
+            let tail = self.add_part(lhs, "list", ast_type);
+            self.non_terminals[tail.index()].kind = NonTerminalKind::Inlined;
+
+            self.add_rule(Rule::new(
+                list,
+                Word::new(vec![item, SymbolRef::NonTerminal(tail)]),
+                "{ let mut __items = res2; __items.insert(0, res1); __items }".to_string(),
+            ))?;
+
+            if trailing == Trailing::Forbid {
+                self.add_rule(Rule::new(
+                    tail,
+                    Word::new(vec![separator, item, SymbolRef::NonTerminal(tail)]),
+                    "{ let mut __items = res3; __items.insert(0, res2); __items }".to_string(),
+                ))?;
+            } else {
+                self.add_rule(Rule::new(
+                    tail,
+                    Word::new(vec![separator, SymbolRef::NonTerminal(list)]),
+                    "res2".to_string(),
+                ))?;
+            }
+            self.add_rule(Rule::new(
+                tail,
+                Word::new(vec![SymbolRef::Epsilon]),
+                "Vec::new()".to_string(),
+            ))?;
+        }
+
+        self.add_rule(Rule::new(
+            list,
+            Word::new(vec![SymbolRef::Epsilon]),
+            "Vec::new()".to_string(),
+        ))?;
+
+        Ok(list)
+    }
+
+    /// Adds a repetition of `item`. Like a list, the generated parser parses
+    /// it with a loop, and its rules `R -> item R | ε` only describe it to the
+    /// analysis.
+    fn add_repeat(
+        &mut self,
+        lhs: NonTerminalRef,
+        item: SymbolRef,
+    ) -> Result<NonTerminalRef, GrammarError> {
+        let ast_type = format!("Vec<{}>", self.result_type(item));
+        let items = self.add_part(lhs, "repetition", ast_type);
+        self.non_terminals[items.index()].kind = NonTerminalKind::Repeat { item };
+
+        self.add_rule(Rule::new(
+            items,
+            Word::new(vec![item, SymbolRef::NonTerminal(items)]),
+            "{ let mut __items = res2; __items.insert(0, res1); __items }".to_string(),
+        ))?;
+        self.add_rule(Rule::new(
+            items,
+            Word::new(vec![SymbolRef::Epsilon]),
+            "Vec::new()".to_string(),
+        ))?;
+
+        Ok(items)
+    }
+}
+
+/// Whether two result types are the same, ignoring how they are spaced.
+fn same_type(a: &str, b: &str) -> bool {
+    let unspaced = |ty: &str| {
+        ty.chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+    };
+    unspaced(a) == unspaced(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grammar::{Grammar, analysis::Analysis};
+
+    #[test]
+    fn operators_become_typed_helpers() {
+        let grammar: Grammar = "terminal a = 'a'; terminal b: u8 = 'b' => { 1 };
+             S = a? b* => { () };"
+            .parse()
+            .unwrap();
+
+        let helpers: Vec<_> = grammar.non_terminals[1..]
+            .iter()
+            .map(|nt| nt.ast_type.as_str())
+            .collect();
+        assert!(helpers.contains(&"Option<char>"));
+        assert!(helpers.contains(&"Vec<u8>"));
+        assert!(Analysis::new(&grammar).all_conflicts(&grammar).is_empty());
+    }
+
+    #[test]
+    fn group_alternatives_must_have_same_type() {
+        let error = "terminal kw = \"if\" | \"do\"; terminal b = 'b';
+             S = (kw | b)*;"
+            .parse::<Grammar>()
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "2:18: Alternatives of a group in S have different types: &'static str and char"
+        );
+    }
+}
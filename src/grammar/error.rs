@@ -0,0 +1,194 @@
+use std::fmt::Display;
+
+use super::{analysis::Lookahead, symbols::refs::SymbolRef, validation::Diagnostic};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Both rules can start with the same byte.
+    FirstFirst,
+    /// One rule can derive epsilon, and a lookahead that may follow the
+    /// non-terminal, possibly the end of the input, also predicts the other
+    /// rule.
+    FirstFollow,
+}
+
+impl Display for ConflictKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConflictKind::FirstFirst => write!(f, "FIRST/FIRST"),
+            ConflictKind::FirstFollow => write!(f, "FIRST/FOLLOW"),
+        }
+    }
+}
+
+/// One of the rules involved in a [`Conflict`] or a [`LeftRecursion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictRule {
+    /// The index into [`Grammar::rules`](super::Grammar::rules).
+    pub index: usize,
+    pub repr: String,
+}
+
+/// Two rules of a non-terminal whose predict sets overlap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub non_terminal: String,
+    pub kind: ConflictKind,
+    pub rules: [ConflictRule; 2],
+    /// The lookaheads for which both rules could be chosen, sorted.
+    pub lookahead: Vec<Lookahead>,
+    /// The lookaheads as displayed, with tokens named after their terminal.
+    pub lookahead_repr: Vec<String>,
+    /// A shortest input prefix that makes the parser choose between the two
+    /// rules, ending with the conflicting lookahead. `None` if the
+    /// non-terminal is unreachable.
+    pub witness: Option<Vec<u8>>,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} conflict in non-terminal {} on {}",
+            self.kind,
+            self.non_terminal,
+            self.lookahead_repr.join(", ")
+        )?;
+        for rule in &self.rules {
+            write!(f, "\n    rule: {}", rule.repr)?;
+        }
+        if let Some(witness) = &self.witness {
+            write!(f, "\n    example input: \"{}\"", witness.escape_ascii())?;
+        }
+        Ok(())
+    }
+}
+
+/// A chain of non-terminals where each one can start with the next one,
+/// and the last one with the first. The generated parser would recurse
+/// forever on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeftRecursion {
+    /// The names of the non-terminals, starting and ending with the same one.
+    pub cycle: Vec<String>,
+    /// The rule leading from each non-terminal of the cycle to the next.
+    pub rules: Vec<ConflictRule>,
+}
+
+impl Display for LeftRecursion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Left recursion {}", self.cycle.join(" -> "))?;
+        for rule in &self.rules {
+            write!(f, "\n    rule: {}", rule.repr)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrammarError {
+    /// A terminal or non-terminal with this name already exists.
+    DuplicateSymbol { name: String },
+    /// No terminal or non-terminal with this name exists.
+    UnknownSymbol { name: String },
+    /// A symbol reference does not point into the grammar.
+    IndexOutOfBounds(SymbolRef),
+    /// The start symbol is not set.
+    MissingStart,
+    /// A non-terminal has no rules, so no parse function can be generated.
+    NoRulesFor { non_terminal: String },
+    /// An empty word was added to a trie terminal.
+    EmptyWord { terminal: String },
+    /// A word was added to a trie terminal that already contains it.
+    WordConflict { terminal: String, word: Vec<u8> },
+    /// The alternatives of a group in a rule of the non-terminal have
+    /// different result types.
+    GroupTypeMismatch {
+        non_terminal: String,
+        first: String,
+        other: String,
+    },
+    /// The pattern of a regex terminal does not parse, matches the empty
+    /// string, or matches nothing at all.
+    InvalidRegex { terminal: String, message: String },
+    /// A skipped byte or comment delimiter clashes with the skip set, see
+    /// [`SkipSet`](super::skip::SkipSet).
+    InvalidSkip { message: String },
+    /// [`Grammar::validate`](super::Grammar::validate) reported errors.
+    Invalid { diagnostics: Vec<Diagnostic> },
+    /// The grammar is not LL(1).
+    NotLL1 { conflicts: Vec<Conflict> },
+    /// The grammar is left-recursive.
+    LeftRecursive { cycles: Vec<LeftRecursion> },
+}
+
+impl Display for GrammarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GrammarError::DuplicateSymbol { name } => {
+                write!(f, "Symbol with name {} already exists", name)
+            }
+            GrammarError::UnknownSymbol { name } => {
+                write!(f, "Symbol with name {} does not exist", name)
+            }
+            GrammarError::IndexOutOfBounds(symbol) => match symbol {
+                SymbolRef::Terminal(t_ref) => {
+                    write!(f, "Terminal index {} out of bounds", t_ref.index())
+                }
+                SymbolRef::NonTerminal(nt_ref) => {
+                    write!(f, "Non-terminal index {} out of bounds", nt_ref.index())
+                }
+                SymbolRef::Epsilon => write!(f, "Symbol {:?} out of bounds", symbol),
+            },
+            GrammarError::MissingStart => write!(f, "Start symbol not set"),
+            GrammarError::NoRulesFor { non_terminal } => {
+                write!(f, "No rules for non-terminal {}", non_terminal)
+            }
+            GrammarError::EmptyWord { terminal } => {
+                write!(f, "Empty word cannot be added to trie {}", terminal)
+            }
+            GrammarError::WordConflict { terminal, word } => write!(
+                f,
+                "Word \"{}\" is already in trie {}",
+                word.escape_ascii(),
+                terminal
+            ),
+            GrammarError::GroupTypeMismatch {
+                non_terminal,
+                first,
+                other,
+            } => write!(
+                f,
+                "Alternatives of a group in {} have different types: {} and {}",
+                non_terminal, first, other
+            ),
+            GrammarError::InvalidRegex { terminal, message } => {
+                write!(f, "Invalid regex of terminal {}: {}", terminal, message)
+            }
+            GrammarError::InvalidSkip { message } => write!(f, "Invalid skip set: {}", message),
+            GrammarError::Invalid { diagnostics } => {
+                write!(f, "Grammar is invalid")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n  {}", diagnostic)?;
+                }
+                Ok(())
+            }
+            GrammarError::NotLL1 { conflicts } => {
+                write!(f, "Grammar is not LL(1)")?;
+                for conflict in conflicts {
+                    write!(f, "\n  {}", conflict)?;
+                }
+                Ok(())
+            }
+            GrammarError::LeftRecursive { cycles } => {
+                write!(f, "Grammar is left-recursive")?;
+                for cycle in cycles {
+                    write!(f, "\n  {}", cycle)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for GrammarError {}
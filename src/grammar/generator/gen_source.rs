@@ -3,24 +3,177 @@ use std::collections::HashSet;
 use super::CodeFile;
 use crate::grammar::{
     Grammar, StrRepr,
-    analysis::predict,
-    symbols::refs::{NonTerminalRef, SymbolRef, TerminalRef},
+    analysis::{Analysis, Lookahead},
+    error::GrammarError,
+    symbols::{
+        non_terminal::{Associativity, Fixity, NonTerminalKind, Operator, Recovery, Trailing},
+        refs::{NonTerminalRef, SymbolRef, TerminalRef},
+    },
 };
 
 pub fn index_to_hex(n: usize) -> String {
     format!("{:#010x}", n)
 }
 
+/// A pattern matching the bytes, with runs of consecutive bytes written as
+/// ranges, e.g. `48..=57 | 95`. The set must not be empty.
+pub fn gen_byte_pattern(bytes: &HashSet<u8>) -> String {
+    let mut bytes: Vec<_> = bytes.iter().copied().collect();
+    bytes.sort();
+
+    let mut ranges: Vec<(u8, u8)> = Vec::new();
+    for byte in bytes {
+        match ranges.last_mut() {
+            Some((_, end)) if *end as u16 + 1 == byte as u16 => *end = byte,
+            _ => ranges.push((byte, byte)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| match end - start {
+            0 => start.to_string(),
+            1 => format!("{} | {}", start, end),
+            _ => format!("{}..={}", start, end),
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// The type of the input the generated parse functions read: bytes, or
+/// tokens if the grammar has a [lexer](Grammar::lexer).
+pub fn input_type(grammar: &Grammar) -> &'static str {
+    if grammar.lexer {
+        "TokenInput<'_, impl Iterator<Item = (Token, Span)>>"
+    } else {
+        "Input<'_, impl Iterator<Item = u8>>"
+    }
+}
+
+/// An expression for the expected set of a generated `ParseError`.
+pub fn gen_expected(expected: impl IntoIterator<Item = String>) -> String {
+    format!(
+        "&[{}]",
+        expected
+            .into_iter()
+            .map(|expected| format!("{:?}", expected))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// The lookaheads as the expected set of an error: bytes like `'a'`, with
+/// runs of consecutive bytes written as ranges like `'a'-'z'`, tokens by the
+/// names of their terminals and `end of input`.
+fn expected_lookaheads(lookaheads: &HashSet<Lookahead>, grammar: &Grammar) -> Vec<String> {
+    let mut runs: Vec<(u8, u8)> = Vec::new();
+    let mut others = Vec::new();
+    for lookahead in sorted(lookaheads) {
+        match lookahead {
+            Lookahead::Byte(byte) => match runs.last_mut() {
+                Some((_, end)) if *end as u16 + 1 == byte as u16 => *end = byte,
+                _ => runs.push((byte, byte)),
+            },
+            Lookahead::Token(_) => others.push(lookahead.repr(grammar)),
+            Lookahead::Eof => others.push("end of input".to_string()),
+        }
+    }
+
+    runs.into_iter()
+        .flat_map(|(start, end)| {
+            let (first, last) = (Lookahead::Byte(start), Lookahead::Byte(end));
+            match end - start {
+                0 => vec![first.to_string()],
+                1 => vec![first.to_string(), last.to_string()],
+                _ => vec![format!("{}-{}", first, last)],
+            }
+        })
+        .chain(others)
+        .collect()
+}
+
+/// The name of the terminal's variant in the generated `Token` and
+/// `TokenKind` enums: its name in CamelCase, with the index appended if the
+/// name of another terminal converts to the same.
+pub fn token_variant(t_ref: TerminalRef, grammar: &Grammar) -> String {
+    let camel_case = |name: &str| {
+        let mut variant: String = name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .flat_map(|part| {
+                let mut chars = part.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_ascii_uppercase())
+                    .into_iter()
+                    .chain(chars)
+            })
+            .collect();
+        if variant.is_empty() || variant.starts_with(|c: char| c.is_ascii_digit()) {
+            variant.insert(0, 'T');
+        }
+        if variant == "Self" {
+            variant.push_str("Token");
+        }
+        variant
+    };
+
+    let variant = camel_case(t_ref.deref(grammar).name());
+    let ambiguous = grammar
+        .iter_terminals()
+        .any(|(t, other)| other != t_ref && camel_case(t.name()) == variant);
+    if ambiguous {
+        format!("{}{}", variant, t_ref.index())
+    } else {
+        variant
+    }
+}
+
+/// Whether the parse functions skip the [skip set](Grammar::skip)
+/// themselves. With a lexer, the tokenizer skips it instead.
+pub fn skips_input(grammar: &Grammar) -> bool {
+    !grammar.lexer && !grammar.skip.is_empty()
+}
+
+/// The statements binding `next` to the peeked lookahead, after skipping
+/// input that the lookahead must not be.
+fn gen_peek(grammar: &Grammar) -> &'static str {
+    if skips_input(grammar) {
+        "_skip(input)?; let next = input.peek();"
+    } else {
+        "let next = input.peek();"
+    }
+}
+
+/// A statement binding the position where the next symbol starts, after
+/// skipping input that does not belong to it.
+fn gen_start(grammar: &Grammar, name: &str) -> String {
+    if skips_input(grammar) {
+        format!("_skip(input)?; let {} = input.pos();", name)
+    } else {
+        format!("let {} = input.pos();", name)
+    }
+}
+
 pub trait GenSource {
-    fn gen_function(&self, grammar: &Grammar, file: &mut CodeFile) -> Result<(), String>;
+    fn gen_function(
+        &self,
+        grammar: &Grammar,
+        analysis: &Analysis,
+        file: &mut CodeFile,
+    ) -> Result<(), GrammarError>;
     fn gen_call(&self, grammar: &Grammar, args: String) -> String;
 }
 
 impl GenSource for SymbolRef {
-    fn gen_function(&self, grammar: &Grammar, file: &mut CodeFile) -> Result<(), String> {
+    fn gen_function(
+        &self,
+        grammar: &Grammar,
+        analysis: &Analysis,
+        file: &mut CodeFile,
+    ) -> Result<(), GrammarError> {
         match self {
-            SymbolRef::Terminal(t) => t.gen_function(grammar, file),
-            SymbolRef::NonTerminal(nt) => nt.gen_function(grammar, file),
+            SymbolRef::Terminal(t) => t.gen_function(grammar, analysis, file),
+            SymbolRef::NonTerminal(nt) => nt.gen_function(grammar, analysis, file),
             SymbolRef::Epsilon => Ok(()),
         }
     }
@@ -35,20 +188,67 @@ impl GenSource for SymbolRef {
 }
 
 impl GenSource for TerminalRef {
-    fn gen_function(&self, grammar: &Grammar, file: &mut CodeFile) -> Result<(), String> {
-        let t = self.deref(grammar);
+    /// With a lexer, the code matching the bytes goes into a `_lex_t_`
+    /// function for the tokenizer, and the parse function only takes the
+    /// terminal's token.
+    fn gen_function(
+        &self,
+        grammar: &Grammar,
+        _analysis: &Analysis,
+        file: &mut CodeFile,
+    ) -> Result<(), GrammarError> {
+        let t = self.try_deref(grammar)?;
+        let hex = index_to_hex(self.index());
 
         file.push_line(format!("// Terminal: {}", t.name()));
-        file.push_line(format!("#[inline]"));
-        file.push_line(format!(
-            "fn _parse_t_{}(input: &mut Peekable<impl Iterator<Item = u8>>) -> Result<{}, String> {{",
-            index_to_hex(self.index()),
-            t.result_type()
-        ));
+        file.push_line("#[inline]");
+        if grammar.lexer {
+            // The tokenizer lexes slices, whose iterators can be cloned
+            file.push_line(format!(
+                "fn _lex_t_{}(input: &mut Input<'_, impl Iterator<Item = u8> + Clone>) -> Result<{}, ParseError> {{",
+                hex,
+                t.result_type()
+            ));
+            t.gen_lex_code(*self, file)?;
+        } else {
+            file.push_line(format!(
+                "fn _parse_t_{}(input: &mut Input<'_, impl Iterator<Item = u8>>) -> Result<{}, ParseError> {{",
+                hex,
+                t.result_type()
+            ));
+            if skips_input(grammar) {
+                file.push_line("    _skip(input)?;");
+            }
+            t.gen_inner_code(*self, file)?;
+        }
 
-        t.gen_inner_code(*self, file)?;
+        file.push_line("}");
 
-        file.push_line(format!("}}"));
+        if grammar.lexer {
+            let variant = token_variant(*self, grammar);
+            file.push_line("#[inline]");
+            file.push_line(format!(
+                "fn _parse_t_{}(input: &mut {}) -> Result<{}, ParseError> {{",
+                hex,
+                input_type(grammar),
+                t.result_type()
+            ));
+            file.push_line(format!(
+                "    match input.next_if(|token| token.kind() == TokenKind::{}) {{",
+                variant
+            ));
+            file.push_line(format!(
+                "        Some(Token::{}(value)) => Ok(value),",
+                variant
+            ));
+            file.push_line(format!(
+                "        _ => Err(input.error({:?}, {})),",
+                t.name(),
+                gen_expected([t.name().to_string()])
+            ));
+            file.push_line("    }");
+            file.push_line("}");
+        }
 
         Ok(())
     }
@@ -59,50 +259,110 @@ impl GenSource for TerminalRef {
 }
 
 impl GenSource for NonTerminalRef {
-    fn gen_function(&self, grammar: &Grammar, file: &mut CodeFile) -> Result<(), String> {
-        let nt = self.deref(grammar);
+    fn gen_function(
+        &self,
+        grammar: &Grammar,
+        analysis: &Analysis,
+        file: &mut CodeFile,
+    ) -> Result<(), GrammarError> {
+        let nt = self.try_deref(grammar)?;
+
+        if let NonTerminalKind::Inlined = nt.kind {
+            return Ok(());
+        }
+
+        // A recovering non-terminal is parsed by a `_try_nt_` function,
+        // which the `_parse_nt_` function recovers from errors of
+        if let Some(recovery) = &nt.recovery {
+            self.gen_recovery(recovery, grammar, analysis, file);
+        }
 
         file.push_line(format!("// Non-terminal: {}", nt.name));
+        // Transforms need not use every result and span bound for them
+        file.push_line("#[allow(unused_variables, unused_assignments)]");
         file.push_line(format!(
-            "fn _parse_nt_{}(input: &mut Peekable<impl Iterator<Item = u8>>) -> Result<{}, String> {{",
+            "fn _{}_nt_{}(input: &mut {}) -> Result<{}, ParseError> {{",
+            if nt.recovery.is_some() {
+                "try"
+            } else {
+                "parse"
+            },
             index_to_hex(self.index()),
+            input_type(grammar),
             nt.ast_type,
         ));
 
-        let mut predicts = HashSet::new();
+        if grammar.get_rules_for_non_terminal(nt).is_empty() {
+            return Err(GrammarError::NoRulesFor {
+                non_terminal: nt.name.clone(),
+            });
+        }
 
-        assert!(
-            grammar.get_rules_for_non_terminal(nt).len() > 0,
-            "No rules for non-terminal {}",
-            nt.name
-        );
+        // The predict set for all rules of a non-terminal must be disjoint
+        let conflicts = analysis.conflicts(*self, grammar);
+        if !conflicts.is_empty() {
+            return Err(GrammarError::NotLL1 { conflicts });
+        }
 
-        file.push_line(format!("    let next = input.peek();"));
+        match nt.kind {
+            NonTerminalKind::Repeat { item } => self.gen_repeat(item, grammar, analysis, file),
+            NonTerminalKind::List {
+                item,
+                separator,
+                trailing,
+            } => self.gen_list(item, separator, trailing, grammar, analysis, file),
+            NonTerminalKind::Operators {
+                operand,
+                ref operators,
+            } => self.gen_operators(operand, operators, grammar, analysis, file),
+            _ => self.gen_rules(grammar, analysis, file),
+        }
 
-        for rule in grammar.get_rules_for_non_terminal(nt) {
-            let predict = predict(rule, grammar);
-            file.push_line(format!("    // Rule: {}", rule.repr(grammar)));
-            file.push_line(format!("    // Predict: {:?}", predict));
+        file.push_line("}");
 
-            // The predict set for all rules of a non-terminal must be disjoint
-            if !predict.is_disjoint(&predicts) {
-                return Err(format!(
-                    "Grammar is not LL(1): Predict sets for rules of non-terminal {} are not disjoint",
-                    nt.name
-                ));
-            }
+        Ok(())
+    }
+
+    fn gen_call(&self, _grammar: &Grammar, args: String) -> String {
+        format!("_parse_nt_{}({})", index_to_hex(self.index()), args)
+    }
+}
+
+impl NonTerminalRef {
+    /// Chooses a rule by the lookahead and parses its symbols. The transform
+    /// sees the span of each symbol's result as `span1`, `span2`, etc., and
+    /// the span of the whole rule as `span`.
+    fn gen_rules(&self, grammar: &Grammar, analysis: &Analysis, file: &mut CodeFile) {
+        file.push_line(format!("    {}", gen_peek(grammar)));
+
+        // When parsing a prefix, a lookahead that no rule accepts is the end
+        // of the input, so the rule predicted by the end of the input comes
+        // last and also takes every other lookahead
+        let (mut rules, end_rules): (Vec<_>, Vec<_>) = grammar
+            .iter_rules_for_non_terminal(*self)
+            .partition(|(i, _)| !analysis.predict(*i).contains(&Lookahead::Eof));
+        rules.extend(end_rules);
 
-            predicts.extend(predict.clone());
+        for (i, rule) in rules {
+            let ends_input = analysis.predict(i).contains(&Lookahead::Eof);
 
+            file.push_line(format!("    // Rule: {}", rule.repr(grammar)));
             file.push_line(format!(
-                "    if {} {{",
-                predict
+                "    // Predict: {}",
+                sorted(analysis.predict(i))
                     .iter()
-                    .map(|t| format!("next == Some(&{})", t))
+                    .map(|lookahead| lookahead.repr(grammar))
                     .collect::<Vec<_>>()
-                    .join(" || ")
+                    .join(", ")
+            ));
+            file.push_line(format!(
+                "    if {}{} {{",
+                gen_condition(analysis.predict(i), grammar),
+                if ends_input { " || input.prefix" } else { "" }
             ));
 
+            file.push_line("        let __start = input.pos();");
+
             let mut i = 1;
 
             for symbol in rule.rhs.iter() {
@@ -110,25 +370,422 @@ impl GenSource for NonTerminalRef {
                     continue;
                 }
                 file.push_line(format!("        // Symbol: {}", symbol.repr(grammar)));
+                file.push_line(format!(
+                    "        {}",
+                    gen_start(grammar, &format!("__start{}", i))
+                ));
                 file.push_line(format!(
                     "        let res{} = {}?;",
                     i,
                     symbol.gen_call(grammar, "input".to_string())
                 ));
+                file.push_line(format!(
+                    "        let span{} = input.span_from(__start{});",
+                    i, i
+                ));
                 i += 1;
             }
 
+            file.push_line("        let span = input.span_from(__start);");
             file.push_line(format!("        return Ok({});", rule.transform));
-            file.push_line(format!("    }}"));
+            file.push_line("    }");
         }
 
-        file.push_line(format!("    return Err(format!(\"Error parsing {}: No matching rule found for {{:?}}\", input.peek()));", nt.name));
-        file.push_line(format!("}}"));
+        let expected: HashSet<_> = grammar
+            .iter_rules_for_non_terminal(*self)
+            .flat_map(|(i, _)| analysis.predict(i))
+            .copied()
+            .collect();
+        file.push_line(format!(
+            "    return Err(input.error({:?}, {}));",
+            self.deref(grammar).describe(),
+            gen_expected(expected_lookaheads(&expected, grammar))
+        ));
+    }
+
+    /// Generates the `_parse_nt_` function of a recovering non-terminal. On
+    /// an error, it skips to the sync set and records the error in the
+    /// input, and then evaluates the error transform.
+    fn gen_recovery(
+        &self,
+        recovery: &Recovery,
+        grammar: &Grammar,
+        analysis: &Analysis,
+        file: &mut CodeFile,
+    ) {
+        let nt = self.deref(grammar);
+        let hex = index_to_hex(self.index());
+        let sync = grammar.sync_set(*self, analysis);
 
-        Ok(())
+        file.push_line(format!("// Recovers from errors in: {}", nt.name));
+        file.push_line("#[allow(unused_variables)]");
+        file.push_line(format!(
+            "fn _parse_nt_{}(input: &mut {}) -> Result<{}, ParseError> {{",
+            hex,
+            input_type(grammar),
+            nt.ast_type,
+        ));
+        file.push_line("    let __start = input.pos();");
+        file.push_line(format!("    let error = match _try_nt_{}(input) {{", hex));
+        file.push_line("        Ok(result) => return Ok(result),");
+        file.push_line("        Err(error) => error,");
+        file.push_line("    };");
+        file.push_line(format!(
+            "    // Sync: {}",
+            sorted(&sync)
+                .iter()
+                .map(|lookahead| lookahead.repr(grammar))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        file.push_line("    loop {");
+        if skips_input(grammar) {
+            // Errors in skipped input are part of the error being recovered
+            // from
+            file.push_line("        let _ = _skip(input);");
+        }
+        file.push_line("        let next = input.peek();");
+        file.push_line(format!("        if {} {{", gen_condition(&sync, grammar)));
+        file.push_line("            break;");
+        file.push_line("        }");
+        file.push_line("        input.next();");
+        file.push_line("    }");
+        file.push_line("    let span = input.span_from(__start);");
+        file.push_line(format!(
+            "    let result = {{ let error = &error; {} }};",
+            recovery.error_transform
+        ));
+        file.push_line("    input.errors.push(error);");
+        file.push_line("    Ok(result)");
+        file.push_line("}");
     }
 
-    fn gen_call(&self, _grammar: &Grammar, args: String) -> String {
-        format!("_parse_nt_{}({})", index_to_hex(self.index()), args)
+    /// Parses a repetition with a loop, which goes on while the lookahead
+    /// is predicted by the rule `R -> item R`, see [`Grammar::add_ebnf_rule`].
+    fn gen_repeat(
+        &self,
+        item: SymbolRef,
+        grammar: &Grammar,
+        analysis: &Analysis,
+        file: &mut CodeFile,
+    ) {
+        let (item_rule, rule) = grammar
+            .iter_rules_for_non_terminal(*self)
+            .find(|(_, rule)| !rule.rhs.iter().all(SymbolRef::is_epsilon))
+            .expect("repetitions have a non-empty rule");
+
+        file.push_line(format!("    // Repetition: {}", rule.repr(grammar)));
+        file.push_line("    let mut items = Vec::new();");
+        file.push_line(format!(
+            "    while {{ {} {} }} {{",
+            gen_peek(grammar),
+            gen_condition(analysis.predict(item_rule), grammar)
+        ));
+        file.push_line(format!(
+            "        items.push({}?);",
+            item.gen_call(grammar, "input".to_string())
+        ));
+        file.push_line("    }");
+        file.push_line("    return Ok(items);");
     }
+
+    /// Parses a separated list with a loop. The lookaheads that continue the
+    /// list are the predict sets of the rules that describe it, see
+    /// [`Grammar::add_ebnf_rule`].
+    fn gen_list(
+        &self,
+        item: SymbolRef,
+        separator: SymbolRef,
+        trailing: Trailing,
+        grammar: &Grammar,
+        analysis: &Analysis,
+        file: &mut CodeFile,
+    ) {
+        let (item_rule, rule) = grammar
+            .iter_rules_for_non_terminal(*self)
+            .find(|(_, rule)| !rule.rhs.iter().all(SymbolRef::is_epsilon))
+            .expect("lists have a non-empty rule");
+        let item_condition = gen_condition(analysis.predict(item_rule), grammar);
+        let parse_item = format!(
+            "items.push({}?);",
+            item.gen_call(grammar, "input".to_string())
+        );
+        let parse_separator = format!("{}?;", separator.gen_call(grammar, "input".to_string()));
+
+        file.push_line(format!("    // List: {}", rule.repr(grammar)));
+        file.push_line("    let mut items = Vec::new();");
+
+        if trailing == Trailing::Require {
+            file.push_line(format!(
+                "    while {{ {} {} }} {{",
+                gen_peek(grammar),
+                item_condition
+            ));
+            file.push_line(format!("        {}", parse_item));
+            file.push_line(format!("        {}", parse_separator));
+            file.push_line("    }");
+            file.push_line("    return Ok(items);");
+            return;
+        }
+
+        // The rule continuing the list after an item, `L' -> separator ...`
+        let Some(SymbolRef::NonTerminal(tail)) = rule.rhs.as_slice().last() else {
+            unreachable!("lists without a required trailing separator end in a tail");
+        };
+        let (separator_rule, _) = grammar
+            .iter_rules_for_non_terminal(*tail)
+            .find(|(_, rule)| !rule.rhs.iter().all(SymbolRef::is_epsilon))
+            .expect("list tails have a non-empty rule");
+        let separator_condition = gen_condition(analysis.predict(separator_rule), grammar);
+
+        if trailing == Trailing::Forbid {
+            file.push_line(format!(
+                "    if {{ {} {} }} {{",
+                gen_peek(grammar),
+                item_condition
+            ));
+            file.push_line(format!("        {}", parse_item));
+            file.push_line(format!(
+                "        while {{ {} {} }} {{",
+                gen_peek(grammar),
+                separator_condition
+            ));
+            file.push_line(format!("            {}", parse_separator));
+            file.push_line(format!("            {}", parse_item));
+            file.push_line("        }");
+            file.push_line("    }");
+        } else {
+            file.push_line(format!(
+                "    while {{ {} {} }} {{",
+                gen_peek(grammar),
+                item_condition
+            ));
+            file.push_line(format!("        {}", parse_item));
+            file.push_line(format!(
+                "        if !{{ {} {} }} {{",
+                gen_peek(grammar),
+                separator_condition
+            ));
+            file.push_line("            break;");
+            file.push_line("        }");
+            file.push_line(format!("        {}", parse_separator));
+            file.push_line("    }");
+        }
+
+        file.push_line("    return Ok(items);");
+    }
+}
+
+impl NonTerminalRef {
+    /// Parses an expression by precedence climbing, in a nested function that
+    /// only accepts operators of at least the given precedence after the
+    /// first operand. As in rules, transforms see the spans of the operands
+    /// and the operator, and the span of the whole expression.
+    fn gen_operators(
+        &self,
+        operand: NonTerminalRef,
+        operators: &[Operator],
+        grammar: &Grammar,
+        analysis: &Analysis,
+        file: &mut CodeFile,
+    ) {
+        let condition = |operator: &Operator| {
+            gen_condition(&analysis.word_first(&[operator.symbol], grammar), grammar)
+        };
+        let parse_operator =
+            |operator: &Operator| operator.symbol.gen_call(grammar, "input".to_string());
+
+        let (prefix, suffix): (Vec<_>, Vec<_>) = operators
+            .iter()
+            .partition(|operator| operator.fixity == Fixity::Prefix);
+
+        file.push_line(format!(
+            "    fn climb(input: &mut {}, min_precedence: u32) -> Result<{}, ParseError> {{",
+            input_type(grammar),
+            self.deref(grammar).ast_type
+        ));
+        file.push_line(format!("        {}", gen_start(grammar, "__start")));
+
+        let operand_call = format!("{}?", operand.gen_call(grammar, "input".to_string()));
+        let operand_predict: HashSet<_> = grammar
+            .iter_rules_for_non_terminal(operand)
+            .flat_map(|(i, _)| analysis.predict(i))
+            .copied()
+            .collect();
+        let operand_condition = gen_condition(&operand_predict, grammar);
+
+        // An expression that starts with neither a prefix operator nor an
+        // operand is an error in the expression, not in the helper
+        // non-terminal of the operands
+        let mut expected = operand_predict.clone();
+        for operator in &prefix {
+            expected.extend(analysis.word_first(&[operator.symbol], grammar));
+        }
+        let operand_error = format!(
+            "return Err(input.error({:?}, {}));",
+            self.deref(grammar).describe(),
+            gen_expected(expected_lookaheads(&expected, grammar))
+        );
+
+        let (binding, span_binding) = if suffix.is_empty() {
+            ("res1", "span1")
+        } else {
+            ("mut res1", "mut span1")
+        };
+        if prefix.is_empty() {
+            file.push_line("        let next = input.peek();");
+            file.push_line(format!("        if !{} {{", operand_condition));
+            file.push_line(format!("            {}", operand_error));
+            file.push_line("        }");
+            file.push_line(format!("        let {} = {};", binding, operand_call));
+            file.push_line(format!(
+                "        let {} = input.span_from(__start);",
+                span_binding
+            ));
+        } else {
+            file.push_line("        let next = input.peek();");
+            for (i, operator) in prefix.iter().enumerate() {
+                file.push_line(format!(
+                    "        {} {} {{",
+                    if i == 0 {
+                        format!("let ({}, {}) = if", binding, span_binding)
+                    } else {
+                        "} else if".to_string()
+                    },
+                    condition(operator)
+                ));
+                file.push_line(format!(
+                    "            // Prefix: {}",
+                    operator.symbol.repr(grammar)
+                ));
+                file.push_line(format!(
+                    "            let res1 = {}?;",
+                    parse_operator(operator)
+                ));
+                file.push_line("            let span1 = input.span_from(__start);");
+                file.push_line(format!("            {}", gen_start(grammar, "__start2")));
+                file.push_line(format!(
+                    "            let res2 = climb(input, {})?;",
+                    operator.precedence
+                ));
+                file.push_line("            let span2 = input.span_from(__start2);");
+                file.push_line("            let span = input.span_from(__start);");
+                file.push_line(format!("            ({}, span)", operator.transform));
+            }
+            file.push_line(format!("        }} else if {} {{", operand_condition));
+            file.push_line(format!(
+                "            ({}, input.span_from(__start))",
+                operand_call
+            ));
+            file.push_line("        } else {");
+            file.push_line(format!("            {}", operand_error));
+            file.push_line("        };");
+        }
+
+        if !suffix.is_empty() {
+            file.push_line("        loop {");
+            file.push_line(format!("            {}", gen_peek(grammar)));
+            for (i, operator) in suffix.iter().enumerate() {
+                file.push_line(format!(
+                    "            {} ({}) && {} >= min_precedence {{",
+                    if i == 0 { "if" } else { "} else if" },
+                    condition(operator),
+                    operator.precedence
+                ));
+                file.push_line(format!(
+                    "                // {}: {}",
+                    if operator.fixity == Fixity::Postfix {
+                        "Postfix"
+                    } else {
+                        "Infix"
+                    },
+                    operator.symbol.repr(grammar)
+                ));
+                file.push_line("                let __start2 = input.pos();");
+                file.push_line(format!(
+                    "                let res2 = {}?;",
+                    parse_operator(operator)
+                ));
+                file.push_line("                let span2 = input.span_from(__start2);");
+                if let Fixity::Infix(associativity) = operator.fixity {
+                    // A left-associative operator leaves operators of the
+                    // same precedence to the loop
+                    let min_precedence = match associativity {
+                        Associativity::Left => operator.precedence + 1,
+                        Associativity::Right => operator.precedence,
+                    };
+                    file.push_line(format!(
+                        "                {}",
+                        gen_start(grammar, "__start3")
+                    ));
+                    file.push_line(format!(
+                        "                let res3 = climb(input, {})?;",
+                        min_precedence
+                    ));
+                    file.push_line("                let span3 = input.span_from(__start3);");
+                }
+                file.push_line("                let span = input.span_from(__start);");
+                // Bound first, since the transform reads the old `res1`
+                file.push_line(format!(
+                    "                let __result = {};",
+                    operator.transform
+                ));
+                file.push_line("                res1 = __result;");
+                file.push_line("                span1 = span;");
+            }
+            file.push_line("            } else {");
+            file.push_line("                break;");
+            file.push_line("            }");
+            file.push_line("        }");
+        }
+
+        file.push_line("        Ok(res1)");
+        file.push_line("    }");
+        file.push_line("    climb(input, 0)");
+    }
+}
+
+/// A condition on `next`, the peeked `Option<&u8>` or `Option<&Token>`,
+/// that holds for the lookaheads.
+fn gen_condition(lookaheads: &HashSet<Lookahead>, grammar: &Grammar) -> String {
+    if lookaheads.is_empty() {
+        return "false".to_string();
+    }
+
+    let bytes: HashSet<u8> = lookaheads
+        .iter()
+        .filter_map(|lookahead| match lookahead {
+            Lookahead::Byte(b) => Some(*b),
+            _ => None,
+        })
+        .collect();
+    let tokens: Vec<String> = sorted(lookaheads)
+        .into_iter()
+        .filter_map(|lookahead| match lookahead {
+            Lookahead::Token(t_ref) => Some(format!("Token::{}(_)", token_variant(t_ref, grammar))),
+            _ => None,
+        })
+        .collect();
+
+    let mut patterns = Vec::new();
+    if !bytes.is_empty() {
+        patterns.push(format!("Some({})", gen_byte_pattern(&bytes)));
+    }
+    if !tokens.is_empty() {
+        patterns.push(format!("Some({})", tokens.join(" | ")));
+    }
+    if lookaheads.contains(&Lookahead::Eof) {
+        if patterns.is_empty() {
+            return "next.is_none()".to_string();
+        }
+        patterns.push("None".to_string());
+    }
+
+    format!("matches!(next, {})", patterns.join(" | "))
+}
+
+fn sorted(lookaheads: &HashSet<Lookahead>) -> Vec<Lookahead> {
+    let mut lookaheads: Vec<_> = lookaheads.iter().copied().collect();
+    lookaheads.sort();
+    lookaheads
 }
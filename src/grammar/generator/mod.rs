@@ -1,11 +1,198 @@
-use std::{fs::File, io::Write};
+use std::{fmt::Display, fs::File, io::Write, path::Path};
 
-use gen_source::GenSource;
+use gen_source::{GenSource, gen_expected, index_to_hex, input_type, skips_input, token_variant};
 
-use super::Grammar;
+use super::{Grammar, NonTerminalRef, analysis::Analysis, error::GrammarError};
 
 pub mod gen_source;
 
+/// Positions and the input wrapper tracking them, the same in every
+/// generated parser. Columns count characters, so continuation bytes of
+/// UTF-8 do not advance them.
+const INPUT_SOURCE: &str = r#"#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// The number of bytes before the position.
+    pub offset: usize,
+    /// Starting at 1.
+    pub line: usize,
+    /// Starting at 1.
+    pub column: usize,
+}
+impl Position {
+    pub const START: Position = Position { offset: 0, line: 1, column: 1 };
+}
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+struct Input<'a, I: Iterator<Item = u8>> {
+    inner: &'a mut Peekable<I>,
+    /// The position of the next byte.
+    pos: Position,
+    /// The position after the last byte that was not skipped.
+    end: Position,
+    /// The errors recovered from, in grammars with recovery.
+    #[allow(dead_code)]
+    errors: Vec<ParseError>,
+    /// Whether a lookahead that no rule accepts ends the input, when parsing
+    /// a prefix.
+    prefix: bool,
+    /// Bytes that were put back to be read again, the next one last.
+    unread: Vec<u8>,
+}
+// Not every parser needs every method
+#[allow(dead_code)]
+impl<'a, I: Iterator<Item = u8>> Input<'a, I> {
+    fn new(inner: &'a mut Peekable<I>) -> Self {
+        Input::at(inner, Position::START)
+    }
+    fn at(inner: &'a mut Peekable<I>, pos: Position) -> Self {
+        Input { inner, pos, end: pos, errors: Vec::new(), prefix: false, unread: Vec::new() }
+    }
+    fn peek(&mut self) -> Option<&u8> {
+        if let Some(byte) = self.unread.last() {
+            return Some(byte);
+        }
+        self.inner.peek()
+    }
+    fn next(&mut self) -> Option<u8> {
+        let byte = match self.unread.pop() {
+            Some(byte) => byte,
+            None => self.inner.next()?,
+        };
+        self.pos.offset += 1;
+        if byte == b'\n' {
+            self.pos.line += 1;
+            self.pos.column = 1;
+        } else if byte & 0xc0 != 0x80 {
+            self.pos.column += 1;
+        }
+        self.end = self.pos;
+        Some(byte)
+    }
+    fn pos(&mut self) -> Position {
+        self.pos
+    }
+    /// Puts back the bytes read since the position, which is not after a
+    /// skipped byte, to read them again.
+    fn rewind(&mut self, pos: Position, bytes: Vec<u8>) {
+        self.unread.extend(bytes.into_iter().rev());
+        self.pos = pos;
+        self.end = pos;
+    }
+    /// The span from the start to the end of the last byte read since.
+    fn span_from(&self, start: Position) -> Span {
+        Span { start, end: self.end.max(start) }
+    }
+    fn found(&mut self) -> Found {
+        self.peek().map_or(Found::Eof, |byte| Found::Byte(*byte))
+    }
+    /// An error at the next byte.
+    fn error(&mut self, parsing: &'static str, expected: &'static [&'static str]) -> ParseError {
+        ParseError { pos: self.pos(), found: self.found(), parsing, expected }
+    }
+}
+"#;
+
+/// The error of the generated parse functions, which says what they
+/// expected and what they found instead.
+const ERROR_SOURCE: &str = r#"#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub pos: Position,
+    /// What was found at the position instead.
+    pub found: Found,
+    /// The name of the non-terminal or terminal being parsed, or what part
+    /// of a non-terminal, e.g. `repetition in S`.
+    pub parsing: &'static str,
+    /// What would have been accepted, e.g. `'a'`, `"if"` or `[0-9]`.
+    pub expected: &'static [&'static str],
+}
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Error parsing {} at {}: expected ", self.parsing, self.pos)?;
+        match self.expected {
+            [expected] => write!(f, "{}", expected)?,
+            expected => write!(f, "one of {}", expected.join(", "))?,
+        }
+        write!(f, " but found {}", self.found)
+    }
+}
+impl std::error::Error for ParseError {}
+"#;
+
+/// Combines the result of an entry point with the errors recovered from, in
+/// grammars with recovery. The error that could not be recovered from, if
+/// any, comes last.
+const WITH_ERRORS_SOURCE: &str = r#"fn _with_errors<T>(result: Result<T, ParseError>, mut errors: Vec<ParseError>) -> (Option<T>, Vec<ParseError>) {
+    match result {
+        Ok(result) => (Some(result), errors),
+        Err(error) => {
+            errors.push(error);
+            (None, errors)
+        }
+    }
+}"#;
+
+/// The input wrapper of parsers with a lexer, reading tokens with their
+/// spans.
+const TOKEN_INPUT_SOURCE: &str = r#"struct TokenInput<'a, I: Iterator<Item = (Token, Span)>> {
+    inner: &'a mut Peekable<I>,
+    /// The end of the last token read.
+    end: Position,
+    /// The errors recovered from, in grammars with recovery.
+    #[allow(dead_code)]
+    errors: Vec<ParseError>,
+    /// Whether a lookahead that no rule accepts ends the input, when parsing
+    /// a prefix.
+    prefix: bool,
+}
+// Not every parser needs every method
+#[allow(dead_code)]
+impl<'a, I: Iterator<Item = (Token, Span)>> TokenInput<'a, I> {
+    fn new(inner: &'a mut Peekable<I>) -> Self {
+        TokenInput { inner, end: Position::START, errors: Vec::new(), prefix: false }
+    }
+    fn peek(&mut self) -> Option<&Token> {
+        self.inner.peek().map(|(token, _)| token)
+    }
+    fn next(&mut self) -> Option<Token> {
+        let (token, span) = self.inner.next()?;
+        self.end = span.end;
+        Some(token)
+    }
+    fn next_if(&mut self, f: impl FnOnce(&Token) -> bool) -> Option<Token> {
+        if self.peek().is_some_and(f) { self.next() } else { None }
+    }
+    /// The start of the next token, or the end of the last one at the end
+    /// of the input.
+    fn pos(&mut self) -> Position {
+        match self.inner.peek() {
+            Some((_, span)) => span.start,
+            None => self.end,
+        }
+    }
+    /// The span from the start to the end of the last token read since.
+    fn span_from(&self, start: Position) -> Span {
+        Span { start, end: self.end.max(start) }
+    }
+    fn found(&mut self) -> Found {
+        self.peek().map_or(Found::Eof, |token| Found::Token(token.kind()))
+    }
+    /// An error at the next token.
+    fn error(&mut self, parsing: &'static str, expected: &'static [&'static str]) -> ParseError {
+        ParseError { pos: self.pos(), found: self.found(), parsing, expected }
+    }
+}
+"#;
+
+#[derive(Default)]
 pub struct CodeFile {
     pub lines: Vec<String>,
 }
@@ -15,21 +202,23 @@ impl CodeFile {
         CodeFile { lines: Vec::new() }
     }
 
-    pub fn push_line(&mut self, line: String) {
-        self.lines.push(line);
-    }
-
-    pub fn to_string(&self) -> String {
-        self.lines.join("\n")
+    pub fn push_line(&mut self, line: impl Into<String>) {
+        self.lines.push(line.into());
     }
 
-    pub fn write_to_file(&self, path: &str) -> std::io::Result<()> {
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
         let mut file = File::create(path)?;
         file.write_all(self.lines.join("\n").as_bytes())?;
         Ok(())
     }
 }
 
+impl Display for CodeFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.lines.join("\n"))
+    }
+}
+
 pub struct CodeGenerator {
     pub grammar: Grammar,
 }
@@ -39,39 +228,509 @@ impl CodeGenerator {
         CodeGenerator { grammar }
     }
 
-    pub fn generate(&self) -> Result<CodeFile, String> {
-        let start = self.grammar.start.expect("Start symbol not set");
+    pub fn generate(&self) -> Result<CodeFile, GrammarError> {
+        // Validation checks every symbol reference, so that the generation
+        // below can dereference them without panicking
+        let diagnostics: Vec<_> = self
+            .grammar
+            .validate()
+            .into_iter()
+            .filter(|d| d.is_error())
+            .collect();
+        if !diagnostics.is_empty() {
+            return Err(GrammarError::Invalid { diagnostics });
+        }
+
+        let start = self.grammar.start.ok_or(GrammarError::MissingStart)?;
+
+        let analysis = Analysis::new(&self.grammar);
+        let cycles = analysis.left_recursion(&self.grammar);
+        if !cycles.is_empty() {
+            return Err(GrammarError::LeftRecursive { cycles });
+        }
+
+        let conflicts = analysis.all_conflicts(&self.grammar);
+        if !conflicts.is_empty() {
+            return Err(GrammarError::NotLL1 { conflicts });
+        }
 
         let mut file = CodeFile::new();
 
-        file.push_line("use std::iter::Peekable;".to_string());
+        file.push_line("use std::iter::Peekable;");
 
-        file.push_line(format!("\n// THIS IS A GENERATED PARSER FILE\n"));
+        file.push_line("\n// THIS IS A GENERATED PARSER FILE\n");
 
         for line in self.grammar.repr().split('\n') {
             file.push_line(format!("// {}", line));
         }
 
-        file.push_line(format!(""));
+        file.push_line("");
+
+        file.push_line(INPUT_SOURCE);
+        self.gen_found(&mut file);
+        file.push_line(ERROR_SOURCE);
+        if self.grammar.lexer {
+            self.gen_tokens(&mut file);
+            file.push_line(TOKEN_INPUT_SOURCE);
+        }
+        if !self.grammar.skip.is_empty() {
+            self.grammar.skip.gen_function(&mut file);
+        }
+        if skips_input(&self.grammar) {
+            file.push_line(
+                "fn _skip(input: &mut Input<'_, impl Iterator<Item = u8>>) -> Result<(), ParseError> {",
+            );
+            file.push_line("    // Skipped input does not belong to the span of what came before");
+            file.push_line("    let end = input.end;");
+            file.push_line("    while _skip_one(input)? {}");
+            file.push_line("    input.end = end;");
+            file.push_line("    Ok(())");
+            file.push_line("}");
+            file.push_line("");
+        }
 
         for t_ref in self.grammar.iter_terminal_refs() {
-            t_ref.gen_function(&self.grammar, &mut file)?;
+            t_ref.gen_function(&self.grammar, &analysis, &mut file)?;
         }
 
         for nt_ref in self.grammar.iter_non_terminal_refs() {
-            nt_ref.gen_function(&self.grammar, &mut file)?;
+            nt_ref.gen_function(&self.grammar, &analysis, &mut file)?;
         }
 
+        if self.grammar.lexer {
+            self.gen_tokenizer(&mut file);
+
+            // Tokenizes the whole input before parsing it
+            let ast_type = &start.deref(&self.grammar).ast_type;
+            file.push_line("#[allow(dead_code)]");
+            if self.grammar.recovers() {
+                file.push_line(format!(
+                    "pub fn parse(input: &mut Peekable<impl Iterator<Item = u8>>) -> (Option<{}>, Vec<ParseError>) {{",
+                    ast_type
+                ));
+                file.push_line("    let (tokens, mut errors) = tokenize(input);");
+                file.push_line(
+                    "    let (result, parse_errors) = parse_tokens(&mut tokens.into_iter().peekable());",
+                );
+                file.push_line("    errors.extend(parse_errors);");
+                file.push_line("    (result, errors)");
+            } else {
+                file.push_line(format!(
+                    "pub fn parse(input: &mut Peekable<impl Iterator<Item = u8>>) -> Result<{}, ParseError> {{",
+                    ast_type
+                ));
+                file.push_line("    let tokens = tokenize(input)?;");
+                file.push_line("    parse_tokens(&mut tokens.into_iter().peekable())");
+            }
+            file.push_line("}");
+        }
+
+        self.gen_entry_points(start, &mut file);
+
+        Ok(file)
+    }
+
+    /// Generates the functions parsing the whole input and a prefix of it.
+    /// With a lexer, the whole input is parsed from tokens with their spans
+    /// by `parse_tokens`. With recovery, they return what they parsed, if
+    /// anything, together with all errors.
+    fn gen_entry_points(&self, start: NonTerminalRef, file: &mut CodeFile) {
+        let start_nt = start.deref(&self.grammar);
+        let (item, input) = if self.grammar.lexer {
+            ("(Token, Span)", "TokenInput")
+        } else {
+            ("u8", "Input")
+        };
+
+        let recovers = self.grammar.recovers();
+        let result_type = |value: &str| {
+            if recovers {
+                format!("(Option<{}>, Vec<ParseError>)", value)
+            } else {
+                format!("Result<{}, ParseError>", value)
+            }
+        };
+        let result = if recovers {
+            "_with_errors(result, input.errors)"
+        } else {
+            "result"
+        };
+
+        // Parses the whole input, which must be derived by the start symbol
         file.push_line(format!(
-            "pub fn parse(input: &mut Peekable<impl Iterator<Item = u8>>) -> Result<{}, String> {{",
-            start.deref(&self.grammar).ast_type
+            "fn _parse_input(input: &mut {}) -> Result<{}, ParseError> {{",
+            input_type(&self.grammar),
+            start_nt.ast_type
         ));
         file.push_line(format!(
-            "    {}",
+            "    let result = {}?;",
             start.gen_call(&self.grammar, "input".to_string())
         ));
-        file.push_line(format!("}}"));
+        if skips_input(&self.grammar) {
+            file.push_line("    _skip(input)?;");
+        }
+        file.push_line("    if input.peek().is_some() {");
+        file.push_line(format!(
+            "        return Err(input.error({:?}, {}));",
+            start_nt.name,
+            gen_expected(["end of input".to_string()])
+        ));
+        file.push_line("    }");
+        file.push_line("    Ok(result)");
+        file.push_line("}");
+        if recovers {
+            file.push_line(WITH_ERRORS_SOURCE);
+        }
 
-        Ok(file)
+        // A parser need not be used through every entry point
+        file.push_line("#[allow(dead_code)]");
+        file.push_line(format!(
+            "pub fn {}(input: &mut Peekable<impl Iterator<Item = {}>>) -> {} {{",
+            if self.grammar.lexer {
+                "parse_tokens"
+            } else {
+                "parse"
+            },
+            item,
+            result_type(&start_nt.ast_type)
+        ));
+        file.push_line(format!("    let mut input = {}::new(input);", input));
+        file.push_line("    let result = _parse_input(&mut input);");
+        file.push_line(format!("    {}", result));
+        file.push_line("}");
+
+        // Parses a prefix of the input that the start symbol derives, where a
+        // lookahead that no rule accepts counts as the end of the input. It
+        // leaves the input after the prefix and also returns how many bytes
+        // the prefix has. With a lexer, the whole input is read to tokenize
+        // it, up to the first byte that starts no token.
+        file.push_line("#[allow(dead_code)]");
+        file.push_line(format!(
+            "pub fn parse_prefix(input: &mut Peekable<impl Iterator<Item = u8>>) -> {} {{",
+            result_type(&format!("({}, usize)", start_nt.ast_type))
+        ));
+        if self.grammar.lexer {
+            file.push_line("    let input: Vec<u8> = input.collect();");
+            if recovers {
+                file.push_line("    let (tokens, errors) = _tokenize(&input, true);");
+            } else {
+                file.push_line("    let tokens = _tokenize(&input, true)?;");
+            }
+            file.push_line("    let mut tokens = tokens.into_iter().peekable();");
+            file.push_line("    let mut input = TokenInput::new(&mut tokens);");
+            if recovers {
+                file.push_line("    input.errors = errors;");
+            }
+        } else {
+            file.push_line("    let mut input = Input::new(input);");
+        }
+        file.push_line("    input.prefix = true;");
+        file.push_line(format!(
+            "    let result = {}.map(|result| (result, input.end.offset));",
+            start.gen_call(&self.grammar, "&mut input".to_string())
+        ));
+        file.push_line(format!("    {}", result));
+        file.push_line("}");
+    }
+
+    /// Generates the `Found` enum of what a parse function found instead of
+    /// what it expected: a byte, a character not in a class if there are
+    /// character classes, a token with a lexer, or the end of the input.
+    fn gen_found(&self, file: &mut CodeFile) {
+        let chars = self
+            .grammar
+            .iter_terminals()
+            .any(|(t, _)| t.decodes_chars());
+
+        file.push_line("#[derive(Debug, Clone, Copy, PartialEq, Eq)]");
+        file.push_line("pub enum Found {");
+        file.push_line("    Byte(u8),");
+        if chars {
+            file.push_line("    Char(char),");
+        }
+        if self.grammar.lexer {
+            file.push_line("    Token(TokenKind),");
+        }
+        file.push_line("    Eof,");
+        file.push_line("}");
+        file.push_line("impl std::fmt::Display for Found {");
+        file.push_line("    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {");
+        file.push_line("        match self {");
+        file.push_line(
+            "            Found::Byte(byte) => write!(f, \"'{}'\", byte.escape_ascii()),",
+        );
+        if chars {
+            file.push_line("            Found::Char(c) => write!(f, \"{:?}\", c),");
+        }
+        if self.grammar.lexer {
+            file.push_line("            Found::Token(kind) => write!(f, \"{}\", kind),");
+        }
+        file.push_line("            Found::Eof => write!(f, \"end of input\"),");
+        file.push_line("        }");
+        file.push_line("    }");
+        file.push_line("}");
+    }
+
+    /// Generates the `Token` enum, with a variant holding the result of each
+    /// terminal, and the `TokenKind` enum without the results.
+    fn gen_tokens(&self, file: &mut CodeFile) {
+        let variants: Vec<_> = self
+            .grammar
+            .iter_terminals()
+            .map(|(t, t_ref)| (token_variant(t_ref, &self.grammar), t.result_type()))
+            .collect();
+
+        file.push_line("#[derive(Debug)]");
+        file.push_line("pub enum Token {");
+        for (variant, result_type) in &variants {
+            file.push_line(format!("    {}({}),", variant, result_type));
+        }
+        file.push_line("}");
+
+        file.push_line("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]");
+        file.push_line("pub enum TokenKind {");
+        for (variant, _) in &variants {
+            file.push_line(format!("    {},", variant));
+        }
+        file.push_line("}");
+
+        // Kinds are displayed by the names of their terminals, like in the
+        // expected sets of errors
+        file.push_line("impl std::fmt::Display for TokenKind {");
+        file.push_line("    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {");
+        file.push_line("        match self {");
+        for ((variant, _), (t, _)) in variants.iter().zip(self.grammar.iter_terminals()) {
+            file.push_line(format!(
+                "            TokenKind::{} => write!(f, {:?}),",
+                variant,
+                t.name()
+            ));
+        }
+        file.push_line("        }");
+        file.push_line("    }");
+        file.push_line("}");
+
+        file.push_line("impl Token {");
+        file.push_line("    pub fn kind(&self) -> TokenKind {");
+        file.push_line("        match self {");
+        for (variant, _) in &variants {
+            file.push_line(format!(
+                "            Token::{}(_) => TokenKind::{},",
+                variant, variant
+            ));
+        }
+        file.push_line("        }");
+        file.push_line("    }");
+        file.push_line("}");
+        file.push_line("");
+    }
+
+    /// Generates `tokenize`, which splits the input into the longest tokens
+    /// any terminal matches, dropping the skip set between them. Of equally
+    /// long tokens, those of literal terminals like keyword tries win over
+    /// the others, and otherwise the terminal declared first wins.
+    ///
+    /// With recovery, `tokenize` skips bytes that start no token and
+    /// returns the tokens together with an error for each run of such bytes.
+    fn gen_tokenizer(&self, file: &mut CodeFile) {
+        let recovers = self.grammar.recovers();
+        let mut terminals: Vec<_> = self.grammar.iter_terminals().collect();
+        terminals.sort_by_key(|(t, _)| !t.is_literal());
+
+        // Runs a lexing function on the rest of the input, which starts at
+        // the position, and returns the end of the match with its result
+        file.push_line("fn _lex<'a, T>(");
+        file.push_line("    input: &'a [u8],");
+        file.push_line("    pos: Position,");
+        file.push_line(
+            "    lex: impl FnOnce(&mut Input<'_, std::iter::Copied<std::slice::Iter<'a, u8>>>) -> Result<T, ParseError>,",
+        );
+        file.push_line(") -> Result<(Position, T), ParseError> {");
+        file.push_line("    let mut rest = input.iter().copied().peekable();");
+        file.push_line("    let mut rest = Input::at(&mut rest, pos);");
+        file.push_line("    let result = lex(&mut rest)?;");
+        file.push_line("    Ok((rest.pos(), result))");
+        file.push_line("}");
+
+        let result_type = if recovers {
+            "(Vec<(Token, Span)>, Vec<ParseError>)"
+        } else {
+            "Result<Vec<(Token, Span)>, ParseError>"
+        };
+        file.push_line("#[allow(dead_code)]");
+        file.push_line(format!(
+            "pub fn tokenize(input: impl IntoIterator<Item = u8>) -> {} {{",
+            result_type
+        ));
+        file.push_line("    let input: Vec<u8> = input.into_iter().collect();");
+        file.push_line("    _tokenize(&input, false)");
+        file.push_line("}");
+
+        // When tokenizing for a prefix, a byte that starts no token ends the
+        // input instead
+        file.push_line(format!(
+            "fn _tokenize(input: &[u8], prefix: bool) -> {} {{",
+            result_type
+        ));
+        file.push_line("    let mut tokens = Vec::new();");
+        file.push_line("    let mut pos = Position::START;");
+        if recovers {
+            file.push_line("    let mut errors = Vec::new();");
+            file.push_line("    // Whether the last byte started no token either");
+            file.push_line("    let mut skipping = false;");
+        }
+        file.push_line("    while pos.offset < input.len() {");
+        file.push_line("        let rest = &input[pos.offset..];");
+        if self.grammar.skip.is_empty() {
+            file.push_line("        let skip_error: Option<ParseError> = None;");
+        } else {
+            // A failed skip is not an error yet, since the bytes can also
+            // start a token
+            file.push_line("        let mut skip_error = None;");
+            file.push_line("        match _lex(rest, pos, |input| _skip_one(input)) {");
+            file.push_line("            Ok((end, true)) => {");
+            file.push_line("                pos = end;");
+            if recovers {
+                file.push_line("                skipping = false;");
+            }
+            file.push_line("                continue;");
+            file.push_line("            }");
+            file.push_line("            Ok((_, false)) => {}");
+            file.push_line("            Err(error) => skip_error = Some(error),");
+            file.push_line("        }");
+        }
+        file.push_line("        let mut longest: Option<(Position, Token)> = None;");
+        for (t, t_ref) in terminals {
+            file.push_line(format!("        // Terminal: {}", t.name()));
+            file.push_line(format!(
+                "        if let Ok((end, result)) = _lex(rest, pos, |input| _lex_t_{}(input))",
+                index_to_hex(t_ref.index())
+            ));
+            file.push_line(
+                "            && longest.as_ref().is_none_or(|(longest, _)| end > *longest)",
+            );
+            file.push_line("        {");
+            file.push_line(format!(
+                "            longest = Some((end, Token::{}(result)));",
+                token_variant(t_ref, &self.grammar)
+            ));
+            file.push_line("        }");
+        }
+        file.push_line("        match longest {");
+        file.push_line("            Some((end, token)) => {");
+        file.push_line("                tokens.push((token, Span { start: pos, end }));");
+        file.push_line("                pos = end;");
+        if recovers {
+            file.push_line("                skipping = false;");
+        }
+        file.push_line("            }");
+        file.push_line("            None if prefix => break,");
+        // Without a token, an error skipping a comment is more telling
+        file.push_line("            None => {");
+        file.push_line("                let error = skip_error.unwrap_or(ParseError {");
+        file.push_line("                    pos,");
+        file.push_line("                    found: Found::Byte(input[pos.offset]),");
+        file.push_line("                    parsing: \"token\",");
+        file.push_line(format!(
+            "                    expected: {},",
+            gen_expected(
+                self.grammar
+                    .iter_terminals()
+                    .map(|(t, _)| t.name().to_string())
+            )
+        ));
+        file.push_line("                });");
+        if recovers {
+            file.push_line("                if !skipping {");
+            file.push_line("                    errors.push(error);");
+            file.push_line("                }");
+            file.push_line("                skipping = true;");
+            file.push_line(
+                "                if let Ok((end, _)) = _lex(rest, pos, |input| Ok(input.next())) {",
+            );
+            file.push_line("                    pos = end;");
+            file.push_line("                }");
+        } else {
+            file.push_line("                return Err(error);");
+        }
+        file.push_line("            }");
+        file.push_line("        }");
+        file.push_line("    }");
+        file.push_line(if recovers {
+            "    (tokens, errors)"
+        } else {
+            "    Ok(tokens)"
+        });
+        file.push_line("}");
+        file.push_line("");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grammar::{
+        Rule, Word,
+        symbols::{
+            non_terminal::{NonTerminal, NonTerminalKind},
+            refs::{SymbolRef, TerminalRef},
+            terminal::ByteTerminal,
+        },
+        validation::Diagnostic,
+    };
+
+    fn grammar() -> (Grammar, NonTerminalRef) {
+        let mut grammar = Grammar::new();
+        let a = grammar
+            .add_terminal(ByteTerminal::from_char('a').unwrap())
+            .unwrap();
+        let s = grammar
+            .add_non_terminal(NonTerminal::new("S".to_string(), "()".to_string()))
+            .unwrap();
+        grammar
+            .add_rule(Rule::new(
+                s,
+                Word::new(vec![SymbolRef::Terminal(a)]),
+                "()".to_string(),
+            ))
+            .unwrap();
+        grammar.set_start(s).unwrap();
+        (grammar, s)
+    }
+
+    fn diagnostics(grammar: Grammar) -> Vec<Diagnostic> {
+        match CodeGenerator::new(grammar).generate() {
+            Err(GrammarError::Invalid { diagnostics }) => diagnostics,
+            _ => panic!("expected the grammar to be invalid"),
+        }
+    }
+
+    #[test]
+    fn rule_with_invalid_symbol_is_an_error() {
+        let (mut grammar, s) = grammar();
+        grammar.rules.push(Rule::new(
+            s,
+            Word::new(vec![SymbolRef::Terminal(TerminalRef::new(7))]),
+            "()".to_string(),
+        ));
+
+        assert_eq!(diagnostics(grammar), [Diagnostic::InvalidRule { rule: 1 }]);
+    }
+
+    #[test]
+    fn list_with_invalid_symbol_is_an_error() {
+        let (mut grammar, s) = grammar();
+        grammar.non_terminals[s.index()].kind = NonTerminalKind::List {
+            item: SymbolRef::Terminal(TerminalRef::new(0)),
+            separator: SymbolRef::NonTerminal(NonTerminalRef::new(7)),
+            trailing: Default::default(),
+        };
+
+        assert_eq!(
+            diagnostics(grammar),
+            [Diagnostic::InvalidNonTerminal {
+                non_terminal: "S".to_string()
+            }]
+        );
     }
 }
@@ -0,0 +1,178 @@
+use std::ops::RangeInclusive;
+
+use crate::grammar::symbols::{
+    non_terminal::{Fixity, Trailing},
+    terminal::{TrieOptions, UnicodeCategory},
+};
+
+/// A line/column position in a grammar file. Both are 1-based.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Position { line, column }
+    }
+}
+
+/// A symbol name together with the position it was written at.
+#[derive(Debug, Clone)]
+pub struct Ident {
+    pub name: String,
+    pub pos: Position,
+}
+
+#[derive(Debug, Clone)]
+pub enum Item {
+    Start(Ident),
+    /// `rewrite name;`, opting into one of the rewrites of
+    /// [`crate::grammar::rewrite`].
+    Rewrite(Ident),
+    /// `lexer;`, tokenizing the input before parsing it, see
+    /// [`crate::grammar::Grammar::lexer`].
+    Lexer(Position),
+    Skip(SkipDecl),
+    Terminal(TerminalDecl),
+    NonTerminal(NonTerminalDecl),
+    Operators(OperatorsDecl),
+    Recover(RecoverDecl),
+}
+
+/// `terminal name: Type = ...;`
+#[derive(Debug, Clone)]
+pub struct TerminalDecl {
+    pub name: Ident,
+    pub result_type: Option<String>,
+    pub kind: TerminalKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum TerminalKind {
+    /// A single byte, written as a char literal, e.g. `'a'`.
+    Byte {
+        byte: u8,
+        result: Option<String>,
+        pos: Position,
+    },
+    /// A set of words, written as string literals, e.g. `"for" | "fork"`.
+    /// The options follow the words, e.g. `with case_insensitive`.
+    Trie {
+        words: Vec<TrieWord>,
+        options: TrieOptions,
+    },
+    /// A regular expression, e.g. `/[a-z_][a-z0-9_]*/`.
+    Regex {
+        pattern: String,
+        result: Option<String>,
+        pos: Position,
+    },
+    /// A Unicode character out of a class, e.g. `u[\p{Letter}_]`.
+    CharClass {
+        ranges: Vec<RangeInclusive<char>>,
+        categories: Vec<UnicodeCategory>,
+        negated: bool,
+        result: Option<String>,
+        pos: Position,
+    },
+    /// A byte out of a class, e.g. `[a-zA-Z_]` or `[^"]`.
+    Class {
+        ranges: Vec<RangeInclusive<u8>>,
+        negated: bool,
+        result: Option<String>,
+        pos: Position,
+    },
+}
+
+/// `skip ...;`, adding to the [skip set](crate::grammar::skip::SkipSet).
+#[derive(Debug, Clone)]
+pub enum SkipDecl {
+    /// A byte class, e.g. `skip [ \t\r\n];`.
+    Bytes {
+        ranges: Vec<RangeInclusive<u8>>,
+        negated: bool,
+        pos: Position,
+    },
+    /// A line comment, e.g. `skip "//";`.
+    LineComment { start: Vec<u8>, pos: Position },
+    /// A block comment, e.g. `skip "/*" "*/" nested;`.
+    BlockComment {
+        start: Vec<u8>,
+        end: Vec<u8>,
+        nested: bool,
+        pos: Position,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct TrieWord {
+    pub word: Vec<u8>,
+    pub result: Option<String>,
+    pub pos: Position,
+}
+
+/// `Name: Type = a b => { ... } | c => { ... };`
+#[derive(Debug, Clone)]
+pub struct NonTerminalDecl {
+    pub name: Ident,
+    pub ast_type: Option<String>,
+    pub alternatives: Vec<Alternative>,
+}
+
+/// `operators Name { left 1: plus => { ... } | minus => { ... }; ... }`
+#[derive(Debug, Clone)]
+pub struct OperatorsDecl {
+    pub name: Ident,
+    pub operators: Vec<OperatorDecl>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OperatorDecl {
+    pub fixity: Fixity,
+    pub precedence: u32,
+    pub symbol: Ident,
+    pub transform: String,
+    pub pos: Position,
+}
+
+/// `recover Name sync a, b => { ... };`, see
+/// [`crate::grammar::symbols::non_terminal::Recovery`].
+#[derive(Debug, Clone)]
+pub struct RecoverDecl {
+    pub name: Ident,
+    pub sync: Option<Vec<Ident>>,
+    pub error_transform: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Alternative {
+    pub elements: Vec<Element>,
+    pub transform: Option<String>,
+    pub pos: Position,
+}
+
+/// A part of an alternative, see [`crate::grammar::ebnf::Element`].
+#[derive(Debug, Clone)]
+pub enum Element {
+    Symbol(Ident),
+    /// `e?`
+    Optional(Box<Element>),
+    /// `e*`
+    Repeat(Box<Element>),
+    /// `e+`
+    RepeatOne(Box<Element>),
+    /// `(a b | c)`
+    Group {
+        alternatives: Vec<Vec<Element>>,
+        pos: Position,
+    },
+    /// `list(item, separator, trailing = ...)`
+    List {
+        item: Box<Element>,
+        separator: Box<Element>,
+        trailing: Trailing,
+        pos: Position,
+    },
+}
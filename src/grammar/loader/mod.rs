@@ -0,0 +1,822 @@
+//! Loading of grammars from the textual `.grammar` format.
+//!
+//! ```text
+//! // Comments use `//` or `/* */`.
+//! start S;
+//!
+//! // Opt into rewriting left-recursive rules and factoring common prefixes
+//! // out of rules, see `Grammar::eliminate_left_recursion` and
+//! // `Grammar::left_factor`. Left recursion is always eliminated first.
+//! rewrite left_recursion;
+//! rewrite left_factoring;
+//!
+//! // Split the input into tokens before parsing, see `Grammar::lexer`. The
+//! // longest match of any terminal is the next token. Of equally long
+//! // matches, single bytes and tries win over the other terminals, and
+//! // then the terminal declared first.
+//! lexer;
+//!
+//! // Skip bytes of a class, comments to the end of the line, and comments
+//! // between two delimiters, possibly nested, before every terminal. No
+//! // terminal may start with a skipped byte, and without a lexer not with
+//! // the first byte of a comment delimiter either.
+//! skip [ \t\r\n];
+//! skip "//";
+//! skip "/*" "*/" nested;
+//!
+//! // A single byte. Without a type the result is the `char` itself.
+//! terminal a = 'a';
+//! terminal nl: u8 = '\n' => { 10 };
+//!
+//! // A byte out of a class of ranges, possibly negated. Without a type the
+//! // result is the matched `u8`, a typed result sees it as `byte`.
+//! terminal letter = [a-zA-Z_];
+//! terminal text: char = [^"\\] => { byte as char };
+//!
+//! // A Unicode character out of a class, decoded from UTF-8. Besides
+//! // characters and ranges, it can contain categories like `\p{Letter}`,
+//! // `\p{Number}` or `\p{Whitespace}`. Without a type the result is the
+//! // matched `char`, a typed result sees it as `c`.
+//! terminal ident_start = u[\p{Letter}_];
+//! terminal not_quote: String = u[^"] => { c.to_string() };
+//!
+//! // A regular expression, see `grammar::regex` for the syntax. Without a
+//! // type the result is the matched `Vec<u8>`, a typed result sees it as
+//! // `bytes`.
+//! terminal ident = /[a-zA-Z_]\w*/;
+//! terminal number: u64 = /\d+/ => { std::str::from_utf8(&bytes).unwrap().parse().unwrap() };
+//!
+//! // A set of words matched with a trie. Without a type the result is the
+//! // matched word as a `&'static str`.
+//! terminal kw: String = "food" => { "food".to_string() } | "foot" => { "foot".to_string() };
+//!
+//! // The longest word matches, and words may be prefixes of other words.
+//! // Options after `with` match ASCII letters in any case, and reject a word
+//! // that is followed by a byte of a class.
+//! terminal op = "=" | "==";
+//! terminal select = "select" with case_insensitive, not_followed_by = [a-zA-Z0-9_];
+//!
+//! // Non-terminals list their rules as alternatives. The transform after `=>`
+//! // sees the results of the symbols as `res1`, `res2`, etc., their spans as
+//! // `span1`, `span2`, etc. and the span of the whole rule as `span`. An
+//! // empty alternative (or `ε`) derives epsilon.
+//! S: (char, char) = a a => { (res1, res2) } | ε => { ('-', '-') };
+//!
+//! // `?`, `*` and `+` make a symbol or a parenthesized group optional or
+//! // repeat it, resulting in an `Option` or a `Vec`. A group results in the
+//! // result of its single symbol or a tuple of the results of its symbols.
+//! L: (Vec<(char, char)>, Option<u8>) = (a a)* nl? => { (res1, res2) };
+//!
+//! // A list of items separated by a separator, resulting in a `Vec` of the
+//! // item results. `trailing` is `forbid` by default, or `allow` or
+//! // `require`.
+//! Args: Vec<char> = list(a, nl, trailing = allow);
+//!
+//! // An operator table turns a non-terminal into an expression parsed by
+//! // precedence climbing, the alternatives of the non-terminal become its
+//! // operands. Each entry is `left`, `right`, `prefix` or `postfix` with a
+//! // precedence, higher binding tighter. Infix transforms see the operands
+//! // as `res1` and `res3`, prefix and postfix ones as `res2` and `res1`.
+//! operators Expr {
+//!     left 1: plus => { res1 + res3 } | minus => { res1 - res3 };
+//!     right 2: caret => { res1.pow(res3 as u32) };
+//!     prefix 3: minus => { -res2 };
+//!     postfix 4: bang => { (1..=res1).product() };
+//! }
+//! Expr: i64 = num | lparen Expr rparen => { res2 };
+//!
+//! // Recover from errors in a non-terminal instead of stopping at the first
+//! // one: skip to a terminal after `sync`, or else to one that can follow the
+//! // non-terminal, and evaluate the error transform in place of its result.
+//! // It sees the error as `error` and the span up to the end of the skipped
+//! // input as `span`. With any recovery, `parse` returns what it parsed, if
+//! // anything, together with all errors.
+//! recover Expr => { 0 };
+//! recover Args sync nl => { Vec::new() };
+//! ```
+//!
+//! A rule without a transform evaluates to `res1` if it has exactly one
+//! symbol and to `()` if it has none. A non-terminal without a type has the
+//! type `()`. If there is no `start` declaration, the first non-terminal is
+//! the start symbol.
+
+use std::{collections::HashMap, fmt::Display, fs, path::Path, str::FromStr};
+
+use ast::{
+    Ident, Item, NonTerminalDecl, OperatorsDecl, Position, RecoverDecl, SkipDecl, TerminalDecl,
+    TerminalKind,
+};
+use parser::Parser;
+
+use super::{
+    Grammar,
+    ebnf::Element,
+    error::GrammarError,
+    skip::BlockComment,
+    symbols::{
+        non_terminal::{NonTerminal, Operator, Recovery},
+        refs::{NonTerminalRef, SymbolRef},
+        terminal::{
+            ByteClassTerminal, ByteTerminal, CharClassTerminal, RegexTerminal, TrieTerminal,
+        },
+    },
+};
+
+pub mod ast;
+pub mod parser;
+
+/// An error in a grammar file, pointing at the offending line and column.
+#[derive(Debug, Clone)]
+pub struct SyntaxError {
+    pub pos: Position,
+    pub message: String,
+}
+
+impl SyntaxError {
+    pub fn new(pos: Position, message: String) -> Self {
+        SyntaxError { pos, message }
+    }
+}
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.pos.line, self.pos.column, self.message)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io { path: String, error: std::io::Error },
+    Syntax { path: String, error: SyntaxError },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io { path, error } => write!(f, "{}: {}", path, error),
+            LoadError::Syntax { path, error } => write!(f, "{}:{}", path, error),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Where the parts of a loaded grammar were declared in its source.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    /// The position of each rule, indexed like [`Grammar::rules`].
+    pub rules: Vec<Position>,
+    /// The position of each symbol declaration, by name.
+    pub symbols: HashMap<String, Position>,
+}
+
+impl FromStr for Grammar {
+    type Err = SyntaxError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        parse_with_source_map(source).map(|(grammar, _)| grammar)
+    }
+}
+
+/// Parses a grammar like [`Grammar::from_str`], additionally returning where
+/// its rules and symbols were declared.
+pub fn parse_with_source_map(source: &str) -> Result<(Grammar, SourceMap), SyntaxError> {
+    let items = Parser::new(source).parse_file()?;
+    GrammarBuilder::default().build(items)
+}
+
+impl Grammar {
+    /// Reads and parses a grammar file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Grammar, LoadError> {
+        let path = path.as_ref();
+        let display = path.display().to_string();
+
+        let source = fs::read_to_string(path).map_err(|error| LoadError::Io {
+            path: display.clone(),
+            error,
+        })?;
+
+        source.parse().map_err(|error| LoadError::Syntax {
+            path: display,
+            error,
+        })
+    }
+}
+
+fn missing_result(name: &Ident, pos: Position) -> SyntaxError {
+    SyntaxError::new(
+        pos,
+        format!(
+            "terminal `{}` has a type and needs a `=> {{ ... }}` result",
+            name.name
+        ),
+    )
+}
+
+fn missing_type(name: &Ident) -> SyntaxError {
+    SyntaxError::new(
+        name.pos,
+        format!(
+            "terminal `{}` has a result and needs a `: Type` annotation",
+            name.name
+        ),
+    )
+}
+
+/// The rewrites a grammar file can opt into, in the order they are applied.
+/// Eliminating left recursion can leave common prefixes behind, so it comes
+/// first.
+const REWRITES: &[&str] = &["left_recursion", "left_factoring"];
+
+/// Turns the parsed items into a [`Grammar`], resolving symbol names.
+#[derive(Default)]
+struct GrammarBuilder {
+    grammar: Grammar,
+    symbols: HashMap<String, (SymbolRef, Position)>,
+    rule_positions: Vec<Position>,
+}
+
+impl GrammarBuilder {
+    fn build(mut self, items: Vec<Item>) -> Result<(Grammar, SourceMap), SyntaxError> {
+        let mut start = None;
+        let mut rewrites = Vec::new();
+        let mut non_terminals = Vec::new();
+        let mut operator_tables = Vec::new();
+        let mut recoveries = Vec::new();
+
+        // Symbols are declared before any rule is resolved, so that rules can
+        // refer to symbols declared further down in the file.
+        for item in items {
+            match item {
+                Item::Start(ident) => {
+                    if start.is_some() {
+                        return Err(SyntaxError::new(
+                            ident.pos,
+                            "duplicate start declaration".to_string(),
+                        ));
+                    }
+                    start = Some(ident);
+                }
+                Item::Rewrite(ident) => {
+                    if !REWRITES.contains(&ident.name.as_str()) {
+                        return Err(SyntaxError::new(
+                            ident.pos,
+                            format!(
+                                "unknown rewrite `{}`, expected one of `{}`",
+                                ident.name,
+                                REWRITES.join("`, `")
+                            ),
+                        ));
+                    }
+                    rewrites.push(ident);
+                }
+                Item::Lexer(pos) => {
+                    if self.grammar.lexer {
+                        return Err(SyntaxError::new(
+                            pos,
+                            "duplicate lexer declaration".to_string(),
+                        ));
+                    }
+                    self.grammar.lexer = true;
+                }
+                Item::Skip(decl) => self.add_skip(decl)?,
+                Item::Terminal(decl) => self.declare_terminal(decl)?,
+                Item::NonTerminal(decl) => {
+                    self.declare_non_terminal(&decl)?;
+                    non_terminals.push(decl);
+                }
+                Item::Operators(decl) => operator_tables.push(decl),
+                Item::Recover(decl) => recoveries.push(decl),
+            }
+        }
+
+        let mut operands = HashMap::new();
+        for decl in &operator_tables {
+            let (nt_ref, operand) = self.add_operators(decl)?;
+            if operands.insert(nt_ref, operand).is_some() {
+                return Err(SyntaxError::new(
+                    decl.name.pos,
+                    format!("duplicate operator table for `{}`", decl.name.name),
+                ));
+            }
+        }
+
+        for decl in &non_terminals {
+            let lhs = match self.symbols[&decl.name.name].0 {
+                SymbolRef::NonTerminal(nt_ref) => nt_ref,
+                _ => unreachable!("non-terminal declarations always map to non-terminals"),
+            };
+            // The alternatives of an expression with operators are its operands
+            self.add_rules(operands.get(&lhs).copied().unwrap_or(lhs), decl)?;
+        }
+
+        for decl in &recoveries {
+            self.add_recovery(decl)?;
+        }
+
+        match start {
+            Some(ident) => match self.symbols.get(&ident.name) {
+                Some((SymbolRef::NonTerminal(nt_ref), _)) => self
+                    .grammar
+                    .set_start(*nt_ref)
+                    .map_err(|err| SyntaxError::new(ident.pos, err.to_string()))?,
+                Some(_) => {
+                    return Err(SyntaxError::new(
+                        ident.pos,
+                        format!("start symbol `{}` is not a non-terminal", ident.name),
+                    ));
+                }
+                None => {
+                    return Err(SyntaxError::new(
+                        ident.pos,
+                        format!("unknown start symbol `{}`", ident.name),
+                    ));
+                }
+            },
+            None => self.grammar.start = self.grammar.iter_non_terminal_refs().next(),
+        }
+
+        for name in REWRITES {
+            let Some(ident) = rewrites.iter().find(|ident| ident.name == *name) else {
+                continue;
+            };
+
+            let origins = match *name {
+                "left_recursion" => self.grammar.eliminate_left_recursion(),
+                _ => self.grammar.left_factor(),
+            }
+            .map_err(|err| {
+                let pos = match &err {
+                    GrammarError::LeftRecursive { cycles } => {
+                        self.rule_positions[cycles[0].rules[0].index]
+                    }
+                    _ => ident.pos,
+                };
+                SyntaxError::new(pos, err.to_string())
+            })?;
+            self.rule_positions = origins
+                .into_iter()
+                .map(|origin| self.rule_positions[origin])
+                .collect();
+        }
+
+        let source_map = SourceMap {
+            rules: self.rule_positions,
+            symbols: self
+                .symbols
+                .into_iter()
+                .map(|(name, (_, pos))| (name, pos))
+                .collect(),
+        };
+
+        Ok((self.grammar, source_map))
+    }
+
+    fn check_unique(&self, name: &str, pos: Position) -> Result<(), SyntaxError> {
+        match self.symbols.get(name) {
+            Some((_, previous)) => Err(SyntaxError::new(
+                pos,
+                format!(
+                    "symbol `{}` is already declared at {}:{}",
+                    name, previous.line, previous.column
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn declare_terminal(&mut self, decl: TerminalDecl) -> Result<(), SyntaxError> {
+        let name = decl.name;
+        self.check_unique(&name.name, name.pos)?;
+
+        let t_ref = match decl.kind {
+            TerminalKind::Byte { byte, result, pos } => {
+                let (result_type, result_expr) = match (decl.result_type, result) {
+                    (Some(ty), Some(expr)) => (ty, expr),
+                    (None, None) if byte.is_ascii() => {
+                        ("char".to_string(), format!("{:?}", byte as char))
+                    }
+                    (None, None) => ("u8".to_string(), byte.to_string()),
+                    (Some(_), None) => return Err(missing_result(&name, pos)),
+                    (None, Some(_)) => return Err(missing_type(&name)),
+                };
+
+                self.grammar.add_terminal(ByteTerminal::new(
+                    name.name.clone(),
+                    byte,
+                    Some(result_expr),
+                    result_type,
+                ))
+            }
+            TerminalKind::Class {
+                ranges,
+                negated,
+                result,
+                pos,
+            } => {
+                let (result_type, result_expr) = match (decl.result_type, result) {
+                    (Some(ty), Some(expr)) => (ty, Some(expr)),
+                    (None, None) => ("u8".to_string(), None),
+                    (Some(_), None) => return Err(missing_result(&name, pos)),
+                    (None, Some(_)) => return Err(missing_type(&name)),
+                };
+
+                self.grammar.add_terminal(ByteClassTerminal::new(
+                    name.name.clone(),
+                    ranges,
+                    negated,
+                    result_expr,
+                    result_type,
+                ))
+            }
+            TerminalKind::CharClass {
+                ranges,
+                categories,
+                negated,
+                result,
+                pos,
+            } => {
+                let (result_type, result_expr) = match (decl.result_type, result) {
+                    (Some(ty), Some(expr)) => (ty, Some(expr)),
+                    (None, None) => ("char".to_string(), None),
+                    (Some(_), None) => return Err(missing_result(&name, pos)),
+                    (None, Some(_)) => return Err(missing_type(&name)),
+                };
+
+                self.grammar.add_terminal(CharClassTerminal::new(
+                    name.name.clone(),
+                    ranges,
+                    categories,
+                    negated,
+                    result_expr,
+                    result_type,
+                ))
+            }
+            TerminalKind::Regex {
+                pattern,
+                result,
+                pos,
+            } => {
+                let (result_type, result_expr) = match (decl.result_type, result) {
+                    (Some(ty), Some(expr)) => (ty, Some(expr)),
+                    (None, None) => ("Vec<u8>".to_string(), None),
+                    (Some(_), None) => return Err(missing_result(&name, pos)),
+                    (None, Some(_)) => return Err(missing_type(&name)),
+                };
+
+                let regex = RegexTerminal::new(name.name.clone(), pattern, result_expr, result_type)
+                    .map_err(|err| SyntaxError::new(pos, err.to_string()))?;
+                self.grammar.add_terminal(regex)
+            }
+            TerminalKind::Trie { words, options } => {
+                let typed = decl.result_type.is_some();
+                let mut trie = TrieTerminal::with_options(
+                    name.name.clone(),
+                    decl.result_type.unwrap_or("&'static str".to_string()),
+                    options,
+                );
+
+                for word in words {
+                    let result = match (typed, word.result) {
+                        (true, Some(expr)) => expr,
+                        (false, None) => match std::str::from_utf8(&word.word) {
+                            Ok(s) => format!("{:?}", s),
+                            Err(_) => {
+                                return Err(SyntaxError::new(
+                                    word.pos,
+                                    "word is not valid UTF-8, so it needs a typed result"
+                                        .to_string(),
+                                ));
+                            }
+                        },
+                        (true, None) => {
+                            return Err(SyntaxError::new(
+                                word.pos,
+                                format!(
+                                    "terminal `{}` has a type, so every word needs a `=> {{ ... }}` result",
+                                    name.name
+                                ),
+                            ));
+                        }
+                        (false, Some(_)) => {
+                            return Err(SyntaxError::new(
+                                name.pos,
+                                format!(
+                                    "terminal `{}` has results and needs a `: Type` annotation",
+                                    name.name
+                                ),
+                            ));
+                        }
+                    };
+
+                    trie.add_word(&word.word, result)
+                        .map_err(|err| SyntaxError::new(word.pos, err.to_string()))?;
+                }
+
+                self.grammar.add_terminal(trie)
+            }
+        }
+        .map_err(|err| SyntaxError::new(name.pos, err.to_string()))?;
+
+        self.symbols
+            .insert(name.name, (SymbolRef::Terminal(t_ref), name.pos));
+        Ok(())
+    }
+
+    fn add_skip(&mut self, decl: SkipDecl) -> Result<(), SyntaxError> {
+        let skip = &mut self.grammar.skip;
+        let (result, pos) = match decl {
+            SkipDecl::Bytes {
+                ranges,
+                negated,
+                pos,
+            } => {
+                let bytes = (0..=u8::MAX)
+                    .filter(|byte| ranges.iter().any(|range| range.contains(byte)) != negated);
+                (skip.add_bytes(bytes), pos)
+            }
+            SkipDecl::LineComment { start, pos } => (skip.add_line_comment(&start), pos),
+            SkipDecl::BlockComment {
+                start,
+                end,
+                nested,
+                pos,
+            } => (
+                skip.add_block_comment(BlockComment { start, end, nested }),
+                pos,
+            ),
+        };
+        result.map_err(|err| SyntaxError::new(pos, err.to_string()))
+    }
+
+    fn declare_non_terminal(&mut self, decl: &NonTerminalDecl) -> Result<(), SyntaxError> {
+        let name = &decl.name;
+        self.check_unique(&name.name, name.pos)?;
+
+        let nt_ref = self
+            .grammar
+            .add_non_terminal(NonTerminal::new(
+                name.name.clone(),
+                decl.ast_type.clone().unwrap_or("()".to_string()),
+            ))
+            .map_err(|err| SyntaxError::new(name.pos, err.to_string()))?;
+
+        self.symbols.insert(
+            name.name.clone(),
+            (SymbolRef::NonTerminal(nt_ref), name.pos),
+        );
+        Ok(())
+    }
+
+    /// Returns the expression non-terminal and its operand non-terminal.
+    fn add_operators(
+        &mut self,
+        decl: &OperatorsDecl,
+    ) -> Result<(NonTerminalRef, NonTerminalRef), SyntaxError> {
+        let name = &decl.name;
+        let nt_ref = match self.symbols.get(&name.name) {
+            Some((SymbolRef::NonTerminal(nt_ref), _)) => *nt_ref,
+            Some(_) => {
+                return Err(SyntaxError::new(
+                    name.pos,
+                    format!(
+                        "operator table for `{}`, which is not a non-terminal",
+                        name.name
+                    ),
+                ));
+            }
+            None => {
+                return Err(SyntaxError::new(
+                    name.pos,
+                    format!("unknown symbol `{}`", name.name),
+                ));
+            }
+        };
+
+        let operators = decl
+            .operators
+            .iter()
+            .map(|operator| match self.symbols.get(&operator.symbol.name) {
+                Some((symbol, _)) => Ok(Operator {
+                    fixity: operator.fixity,
+                    precedence: operator.precedence,
+                    symbol: *symbol,
+                    transform: operator.transform.clone(),
+                }),
+                None => Err(SyntaxError::new(
+                    operator.symbol.pos,
+                    format!("unknown symbol `{}`", operator.symbol.name),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let operand = self
+            .grammar
+            .add_operators(nt_ref, operators)
+            .map_err(|err| SyntaxError::new(name.pos, err.to_string()))?;
+        self.rule_positions
+            .resize(self.grammar.rules.len(), name.pos);
+
+        Ok((nt_ref, operand))
+    }
+
+    fn add_recovery(&mut self, decl: &RecoverDecl) -> Result<(), SyntaxError> {
+        let name = &decl.name;
+        let nt_ref = match self.symbols.get(&name.name) {
+            Some((SymbolRef::NonTerminal(nt_ref), _)) => *nt_ref,
+            Some(_) => {
+                return Err(SyntaxError::new(
+                    name.pos,
+                    format!("recovery for `{}`, which is not a non-terminal", name.name),
+                ));
+            }
+            None => {
+                return Err(SyntaxError::new(
+                    name.pos,
+                    format!("unknown symbol `{}`", name.name),
+                ));
+            }
+        };
+        if nt_ref.deref(&self.grammar).recovery.is_some() {
+            return Err(SyntaxError::new(
+                name.pos,
+                format!("duplicate recovery for `{}`", name.name),
+            ));
+        }
+
+        let sync = decl
+            .sync
+            .as_ref()
+            .map(|terminals| {
+                terminals
+                    .iter()
+                    .map(|ident| match self.symbols.get(&ident.name) {
+                        Some((SymbolRef::Terminal(t_ref), _)) => Ok(*t_ref),
+                        Some(_) => Err(SyntaxError::new(
+                            ident.pos,
+                            format!("sync symbol `{}` is not a terminal", ident.name),
+                        )),
+                        None => Err(SyntaxError::new(
+                            ident.pos,
+                            format!("unknown symbol `{}`", ident.name),
+                        )),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+
+        self.grammar
+            .set_recovery(
+                nt_ref,
+                Recovery {
+                    sync,
+                    error_transform: decl.error_transform.clone(),
+                },
+            )
+            .map_err(|err| SyntaxError::new(name.pos, err.to_string()))
+    }
+
+    fn add_rules(
+        &mut self,
+        lhs: NonTerminalRef,
+        decl: &NonTerminalDecl,
+    ) -> Result<(), SyntaxError> {
+        for alternative in &decl.alternatives {
+            let elements = alternative
+                .elements
+                .iter()
+                .map(|element| self.resolve(element))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let transform = match &alternative.transform {
+                Some(transform) => transform.clone(),
+                None if elements.is_empty() => "()".to_string(),
+                None if elements.len() == 1 => "res1".to_string(),
+                None => {
+                    return Err(SyntaxError::new(
+                        alternative.pos,
+                        format!(
+                            "a rule with {} symbols needs a `=> {{ ... }}` transform",
+                            elements.len()
+                        ),
+                    ));
+                }
+            };
+
+            self.grammar
+                .add_ebnf_rule(lhs, elements, transform)
+                .map_err(|err| SyntaxError::new(alternative.pos, err.to_string()))?;
+            // Helper rules for EBNF operators point at the alternative, too
+            self.rule_positions
+                .resize(self.grammar.rules.len(), alternative.pos);
+        }
+
+        Ok(())
+    }
+
+    fn resolve(&self, element: &ast::Element) -> Result<Element, SyntaxError> {
+        Ok(match element {
+            ast::Element::Symbol(ident) => match self.symbols.get(&ident.name) {
+                Some((symbol, _)) => Element::Symbol(*symbol),
+                None => {
+                    return Err(SyntaxError::new(
+                        ident.pos,
+                        format!("unknown symbol `{}`", ident.name),
+                    ));
+                }
+            },
+            ast::Element::Optional(inner) => Element::Optional(Box::new(self.resolve(inner)?)),
+            ast::Element::Repeat(inner) => Element::Repeat(Box::new(self.resolve(inner)?)),
+            ast::Element::RepeatOne(inner) => Element::RepeatOne(Box::new(self.resolve(inner)?)),
+            ast::Element::Group { alternatives, .. } => Element::Group(
+                alternatives
+                    .iter()
+                    .map(|elements| {
+                        elements
+                            .iter()
+                            .map(|element| self.resolve(element))
+                            .collect::<Result<Vec<_>, _>>()
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            ast::Element::List {
+                item,
+                separator,
+                trailing,
+                ..
+            } => Element::List {
+                item: Box::new(self.resolve(item)?),
+                separator: Box::new(self.resolve(separator)?),
+                trailing: *trailing,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> String {
+        source.parse::<Grammar>().err().unwrap().to_string()
+    }
+
+    #[test]
+    fn loads_symbols_and_rules() {
+        let grammar: Grammar = "terminal a = 'a';
+             terminal b = \"bc\";
+             start T;
+             S = a;
+             T: u8 = a b S => { 1 } | ε => { 0 };"
+            .parse()
+            .unwrap();
+
+        assert_eq!(grammar.terminals.len(), 2);
+        assert_eq!(grammar.terminals[1].name(), "b");
+        let names: Vec<_> = grammar.non_terminals.iter().map(|nt| &nt.name).collect();
+        assert_eq!(names, ["S", "T"]);
+        assert_eq!(grammar.start.map(|start| start.index()), Some(1));
+        assert_eq!(grammar.rules.len(), 3);
+        assert_eq!(grammar.rules[1].rhs.as_slice().len(), 3);
+        assert_eq!(grammar.rules[2].rhs.as_slice(), [SymbolRef::Epsilon]);
+    }
+
+    #[test]
+    fn reports_line_and_column() {
+        assert_eq!(
+            error("terminal a = 'a';\nS = a b;"),
+            "2:7: unknown symbol `b`"
+        );
+        assert_eq!(
+            error("terminal a = 'a';\n  terminal a = 'b';"),
+            "2:12: symbol `a` is already declared at 1:10"
+        );
+        assert_eq!(
+            error("terminal a = \"abc;"),
+            "1:14: unterminated string literal"
+        );
+        assert_eq!(
+            error("terminal a = 'a'\nS = a;"),
+            "2:1: expected `;`, found `S`"
+        );
+    }
+
+    #[test]
+    fn loads_byte_classes() {
+        let grammar: Grammar = r"terminal ident = [a-zA-Z_\]];
+             terminal other = [^0-9-];
+             S = ident other => { () };"
+            .parse()
+            .unwrap();
+        assert_eq!(grammar.terminals.len(), 2);
+
+        assert_eq!(
+            error("terminal a = [z-a];"),
+            "1:15: byte range is out of order"
+        );
+        assert_eq!(error("terminal a = [];"), "1:14: empty byte class");
+        assert_eq!(error("terminal a = [a-z;"), "1:14: unterminated byte class");
+    }
+}
@@ -0,0 +1,1145 @@
+use std::ops::RangeInclusive;
+
+use super::{
+    SyntaxError,
+    ast::{
+        Alternative, Element, Ident, Item, NonTerminalDecl, OperatorDecl, OperatorsDecl, Position,
+        RecoverDecl, SkipDecl, TerminalDecl, TerminalKind, TrieWord,
+    },
+};
+use crate::grammar::symbols::{
+    non_terminal::{Associativity, Fixity, Trailing},
+    terminal::{TrieOptions, UnicodeCategory},
+};
+
+/// The ranges, categories and negation of a Unicode class.
+type CharClass = (Vec<RangeInclusive<char>>, Vec<UnicodeCategory>, bool);
+
+const KEYWORDS: &[&str] = &[
+    "lexer",
+    "list",
+    "operators",
+    "recover",
+    "rewrite",
+    "skip",
+    "start",
+    "terminal",
+];
+
+/// A hand-written recursive descent parser for the grammar file format.
+/// It works directly on characters, since AST types and transforms are
+/// Rust fragments that are copied verbatim into the generated parser.
+pub struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Parser {
+    pub fn new(source: &str) -> Self {
+        Parser {
+            chars: source.chars().collect(),
+            index: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub fn parse_file(mut self) -> Result<Vec<Item>, SyntaxError> {
+        let mut items = Vec::new();
+
+        loop {
+            self.skip_trivia()?;
+            if self.peek().is_none() {
+                return Ok(items);
+            }
+            items.push(self.parse_item()?);
+        }
+    }
+
+    fn parse_item(&mut self) -> Result<Item, SyntaxError> {
+        let ident = self.parse_ident()?;
+
+        match ident.name.as_str() {
+            "start" => {
+                let name = self.parse_symbol_name()?;
+                self.expect(';')?;
+                Ok(Item::Start(name))
+            }
+            "rewrite" => {
+                let name = self.parse_ident()?;
+                self.expect(';')?;
+                Ok(Item::Rewrite(name))
+            }
+            "lexer" => {
+                self.expect(';')?;
+                Ok(Item::Lexer(ident.pos))
+            }
+            "skip" => Ok(Item::Skip(self.parse_skip()?)),
+            "terminal" => Ok(Item::Terminal(self.parse_terminal()?)),
+            "operators" => Ok(Item::Operators(self.parse_operators()?)),
+            "recover" => Ok(Item::Recover(self.parse_recover()?)),
+            _ => Ok(Item::NonTerminal(self.parse_non_terminal(ident)?)),
+        }
+    }
+
+    fn parse_terminal(&mut self) -> Result<TerminalDecl, SyntaxError> {
+        let name = self.parse_symbol_name()?;
+        let result_type = self.parse_type_annotation()?;
+        self.expect('=')?;
+
+        self.skip_trivia()?;
+        let pos = self.pos();
+        let kind = match self.peek() {
+            Some('\'') => {
+                let byte = self.parse_byte_literal()?;
+                TerminalKind::Byte {
+                    byte,
+                    result: self.parse_result()?,
+                    pos,
+                }
+            }
+            Some('"') => {
+                let mut words = Vec::new();
+                loop {
+                    self.skip_trivia()?;
+                    let pos = self.pos();
+                    let word = self.parse_string_literal()?;
+                    words.push(TrieWord {
+                        word,
+                        result: self.parse_result()?,
+                        pos,
+                    });
+
+                    if !self.eat('|')? {
+                        break;
+                    }
+                }
+                TerminalKind::Trie {
+                    words,
+                    options: self.parse_trie_options()?,
+                }
+            }
+            Some('[') => {
+                let (ranges, negated) = self.parse_byte_class()?;
+                TerminalKind::Class {
+                    ranges,
+                    negated,
+                    result: self.parse_result()?,
+                    pos,
+                }
+            }
+            Some('u') if self.peek_at(1) == Some('[') => {
+                self.bump();
+                let (ranges, categories, negated) = self.parse_char_class()?;
+                TerminalKind::CharClass {
+                    ranges,
+                    categories,
+                    negated,
+                    result: self.parse_result()?,
+                    pos,
+                }
+            }
+            Some('/') => TerminalKind::Regex {
+                pattern: self.parse_regex_literal()?,
+                result: self.parse_result()?,
+                pos,
+            },
+            _ => {
+                return Err(self.unexpected("a char or string literal, a byte class or a regex"));
+            }
+        };
+
+        self.expect(';')?;
+
+        Ok(TerminalDecl {
+            name,
+            result_type,
+            kind,
+        })
+    }
+
+    /// Parses what follows `skip`: a byte class, the start delimiter of a
+    /// line comment, or the delimiters of a block comment followed by an
+    /// optional `nested`.
+    fn parse_skip(&mut self) -> Result<SkipDecl, SyntaxError> {
+        self.skip_trivia()?;
+        let pos = self.pos();
+        let decl = match self.peek() {
+            Some('[') => {
+                let (ranges, negated) = self.parse_byte_class()?;
+                SkipDecl::Bytes {
+                    ranges,
+                    negated,
+                    pos,
+                }
+            }
+            Some('"') => {
+                let start = self.parse_string_literal()?;
+                self.skip_trivia()?;
+                if self.peek() == Some('"') {
+                    let end = self.parse_string_literal()?;
+                    self.skip_trivia()?;
+                    let nested = self.peek().is_some_and(is_ident_start);
+                    if nested {
+                        let ident = self.parse_ident()?;
+                        if ident.name != "nested" {
+                            return Err(SyntaxError::new(
+                                ident.pos,
+                                format!("expected `nested`, found `{}`", ident.name),
+                            ));
+                        }
+                    }
+                    SkipDecl::BlockComment {
+                        start,
+                        end,
+                        nested,
+                        pos,
+                    }
+                } else {
+                    SkipDecl::LineComment { start, pos }
+                }
+            }
+            _ => return Err(self.unexpected("a byte class or a string literal")),
+        };
+
+        self.expect(';')?;
+        Ok(decl)
+    }
+
+    fn parse_non_terminal(&mut self, name: Ident) -> Result<NonTerminalDecl, SyntaxError> {
+        let ast_type = self.parse_type_annotation()?;
+        self.expect('=')?;
+
+        let mut alternatives = Vec::new();
+        loop {
+            alternatives.push(self.parse_alternative()?);
+
+            if !self.eat('|')? {
+                break;
+            }
+        }
+
+        self.expect(';')?;
+
+        Ok(NonTerminalDecl {
+            name,
+            ast_type,
+            alternatives,
+        })
+    }
+
+    /// Parses an operator table. Each entry gives a fixity and a precedence
+    /// to one or more operators: `left 1: plus => { ... } | minus => { ... };`
+    fn parse_operators(&mut self) -> Result<OperatorsDecl, SyntaxError> {
+        let name = self.parse_symbol_name()?;
+        self.expect('{')?;
+
+        let mut operators = Vec::new();
+        while !self.eat('}')? {
+            let fixity_ident = self.parse_ident()?;
+            let fixity = match fixity_ident.name.as_str() {
+                "left" => Fixity::Infix(Associativity::Left),
+                "right" => Fixity::Infix(Associativity::Right),
+                "prefix" => Fixity::Prefix,
+                "postfix" => Fixity::Postfix,
+                _ => {
+                    return Err(SyntaxError::new(
+                        fixity_ident.pos,
+                        format!(
+                            "expected `left`, `right`, `prefix` or `postfix`, found `{}`",
+                            fixity_ident.name
+                        ),
+                    ));
+                }
+            };
+            let precedence = self.parse_number()?;
+            self.expect(':')?;
+
+            loop {
+                self.skip_trivia()?;
+                let pos = self.pos();
+                let symbol = self.parse_symbol_name()?;
+                let Some(transform) = self.parse_result()? else {
+                    return Err(self.unexpected("a `=> { ... }` transform"));
+                };
+                operators.push(OperatorDecl {
+                    fixity,
+                    precedence,
+                    symbol,
+                    transform,
+                    pos,
+                });
+
+                if !self.eat('|')? {
+                    break;
+                }
+            }
+
+            self.expect(';')?;
+        }
+
+        Ok(OperatorsDecl { name, operators })
+    }
+
+    /// Parses what follows `recover`: the non-terminal, an optional `sync`
+    /// with a list of terminals, and the error transform.
+    fn parse_recover(&mut self) -> Result<RecoverDecl, SyntaxError> {
+        let name = self.parse_symbol_name()?;
+
+        self.skip_trivia()?;
+        let sync = if self.peek().is_some_and(is_ident_start) {
+            let ident = self.parse_ident()?;
+            if ident.name != "sync" {
+                return Err(SyntaxError::new(
+                    ident.pos,
+                    format!("expected `sync` or `=>`, found `{}`", ident.name),
+                ));
+            }
+
+            let mut terminals = vec![self.parse_symbol_name()?];
+            while self.eat(',')? {
+                terminals.push(self.parse_symbol_name()?);
+            }
+            Some(terminals)
+        } else {
+            None
+        };
+
+        let Some(error_transform) = self.parse_result()? else {
+            return Err(self.unexpected("a `=> { ... }` error transform"));
+        };
+        self.expect(';')?;
+
+        Ok(RecoverDecl {
+            name,
+            sync,
+            error_transform,
+        })
+    }
+
+    fn parse_alternative(&mut self) -> Result<Alternative, SyntaxError> {
+        self.skip_trivia()?;
+        let pos = self.pos();
+
+        Ok(Alternative {
+            elements: self.parse_elements()?,
+            transform: self.parse_result()?,
+            pos,
+        })
+    }
+
+    /// Parses symbols, groups and their EBNF operators up to the end of an
+    /// alternative.
+    fn parse_elements(&mut self) -> Result<Vec<Element>, SyntaxError> {
+        let mut elements = Vec::new();
+
+        loop {
+            self.skip_trivia()?;
+            let mut element = match self.peek() {
+                Some('ε') => {
+                    self.bump();
+                    continue;
+                }
+                Some('(') => {
+                    let pos = self.pos();
+                    self.bump();
+
+                    let mut alternatives = vec![self.parse_elements()?];
+                    while self.eat('|')? {
+                        alternatives.push(self.parse_elements()?);
+                    }
+                    self.expect(')')?;
+
+                    Element::Group { alternatives, pos }
+                }
+                Some(c) if is_ident_start(c) => {
+                    let ident = self.parse_ident()?;
+                    if ident.name == "list" {
+                        self.parse_list(ident.pos)?
+                    } else {
+                        Element::Symbol(self.check_symbol_name(ident)?)
+                    }
+                }
+                _ => return Ok(elements),
+            };
+
+            loop {
+                self.skip_trivia()?;
+                element = match self.peek() {
+                    Some('?') => Element::Optional(Box::new(element)),
+                    Some('*') => Element::Repeat(Box::new(element)),
+                    Some('+') => Element::RepeatOne(Box::new(element)),
+                    _ => break,
+                };
+                self.bump();
+            }
+
+            elements.push(element);
+        }
+    }
+
+    /// Parses the arguments of `list(item, separator, trailing = ...)`. The
+    /// trailing argument is optional.
+    fn parse_list(&mut self, pos: Position) -> Result<Element, SyntaxError> {
+        self.expect('(')?;
+        let item = self.parse_list_argument()?;
+        self.expect(',')?;
+        let separator = self.parse_list_argument()?;
+
+        let mut trailing = Trailing::default();
+        if self.eat(',')? {
+            self.skip_trivia()?;
+            let name = self.parse_ident()?;
+            if name.name != "trailing" {
+                return Err(SyntaxError::new(
+                    name.pos,
+                    format!("expected `trailing`, found `{}`", name.name),
+                ));
+            }
+            self.expect('=')?;
+
+            self.skip_trivia()?;
+            let value = self.parse_ident()?;
+            trailing = match value.name.as_str() {
+                "allow" => Trailing::Allow,
+                "forbid" => Trailing::Forbid,
+                "require" => Trailing::Require,
+                _ => {
+                    return Err(SyntaxError::new(
+                        value.pos,
+                        format!(
+                            "expected `allow`, `forbid` or `require`, found `{}`",
+                            value.name
+                        ),
+                    ));
+                }
+            };
+        }
+        self.expect(')')?;
+
+        Ok(Element::List {
+            item: Box::new(item),
+            separator: Box::new(separator),
+            trailing,
+            pos,
+        })
+    }
+
+    /// Parses the item or separator of a list. Several elements are grouped.
+    fn parse_list_argument(&mut self) -> Result<Element, SyntaxError> {
+        self.skip_trivia()?;
+        let pos = self.pos();
+        let mut elements = self.parse_elements()?;
+
+        if elements.len() == 1 {
+            Ok(elements.remove(0))
+        } else if elements.is_empty() {
+            Err(self.unexpected("a list item or separator"))
+        } else {
+            Ok(Element::Group {
+                alternatives: vec![elements],
+                pos,
+            })
+        }
+    }
+
+    /// Parses an optional `=> { ... }` suffix.
+    fn parse_result(&mut self) -> Result<Option<String>, SyntaxError> {
+        self.skip_trivia()?;
+        if self.peek() == Some('=') && self.peek_at(1) == Some('>') {
+            self.bump();
+            self.bump();
+            Ok(Some(self.parse_code_block()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parses an optional `: Type` annotation. The type extends up to the
+    /// next `=` or `;` that is not nested inside brackets.
+    fn parse_type_annotation(&mut self) -> Result<Option<String>, SyntaxError> {
+        if !self.eat(':')? {
+            return Ok(None);
+        }
+
+        self.skip_trivia()?;
+        let pos = self.pos();
+        let mut depth = 0usize;
+        let mut text = String::new();
+
+        loop {
+            match self.peek() {
+                None => return Err(self.unexpected("`=` after the type")),
+                Some('=' | ';') if depth == 0 => break,
+                Some('-') if self.peek_at(1) == Some('>') => {
+                    self.bump();
+                    self.bump();
+                    text.push_str("->");
+                }
+                Some(c @ ('(' | '[' | '{' | '<')) => {
+                    depth += 1;
+                    self.bump();
+                    text.push(c);
+                }
+                Some(c @ (')' | ']' | '}' | '>')) => {
+                    if depth == 0 {
+                        return Err(SyntaxError::new(
+                            self.pos(),
+                            format!("unbalanced `{}` in type", c),
+                        ));
+                    }
+                    depth -= 1;
+                    self.bump();
+                    text.push(c);
+                }
+                Some(c) => {
+                    self.bump();
+                    text.push(c);
+                }
+            }
+        }
+
+        let ty = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if ty.is_empty() {
+            return Err(SyntaxError::new(
+                pos,
+                "expected a type after `:`".to_string(),
+            ));
+        }
+
+        Ok(Some(ty))
+    }
+
+    /// Parses a `{ ... }` block of Rust code and returns its trimmed contents.
+    fn parse_code_block(&mut self) -> Result<String, SyntaxError> {
+        self.skip_trivia()?;
+        let start = self.pos();
+        if self.peek() != Some('{') {
+            return Err(self.unexpected("`{`"));
+        }
+        self.bump();
+
+        let begin = self.index;
+        let mut depth = 1usize;
+
+        loop {
+            match self.peek() {
+                None => return Err(SyntaxError::new(start, "unclosed `{`".to_string())),
+                Some('{') => {
+                    depth += 1;
+                    self.bump();
+                }
+                Some('}') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                    self.bump();
+                }
+                Some('"') => self.skip_rust_string()?,
+                Some('\'') => self.skip_rust_char_or_lifetime(),
+                Some('/') if matches!(self.peek_at(1), Some('/' | '*')) => self.skip_comment()?,
+                Some(_) => self.bump(),
+            }
+        }
+
+        let code: String = self.chars[begin..self.index].iter().collect();
+        self.bump();
+
+        Ok(code.trim().to_string())
+    }
+
+    fn skip_rust_string(&mut self) -> Result<(), SyntaxError> {
+        let start = self.pos();
+        self.bump();
+        loop {
+            match self.peek() {
+                None => return Err(SyntaxError::new(start, "unterminated string".to_string())),
+                Some('\\') => {
+                    self.bump();
+                    self.bump();
+                }
+                Some('"') => {
+                    self.bump();
+                    return Ok(());
+                }
+                Some(_) => self.bump(),
+            }
+        }
+    }
+
+    /// Distinguishes `'a'` and `'\n'` from lifetimes such as `'static`.
+    fn skip_rust_char_or_lifetime(&mut self) {
+        self.bump();
+        if self.peek() == Some('\\') {
+            while let Some(c) = self.peek() {
+                self.bump();
+                if c == '\'' {
+                    break;
+                }
+            }
+        } else if self.peek_at(1) == Some('\'') {
+            self.bump();
+            self.bump();
+        }
+    }
+
+    fn parse_ident(&mut self) -> Result<Ident, SyntaxError> {
+        self.skip_trivia()?;
+        let pos = self.pos();
+
+        match self.peek() {
+            Some(c) if is_ident_start(c) => {}
+            _ => return Err(self.unexpected("an identifier")),
+        }
+
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if !is_ident_continue(c) {
+                break;
+            }
+            name.push(c);
+            self.bump();
+        }
+
+        Ok(Ident { name, pos })
+    }
+
+    fn parse_number(&mut self) -> Result<u32, SyntaxError> {
+        self.skip_trivia()?;
+        let pos = self.pos();
+
+        let mut digits = String::new();
+        while let Some(c) = self.peek().filter(char::is_ascii_digit) {
+            digits.push(c);
+            self.bump();
+        }
+
+        if digits.is_empty() {
+            return Err(self.unexpected("a number"));
+        }
+        digits
+            .parse()
+            .map_err(|_| SyntaxError::new(pos, format!("number `{}` is too large", digits)))
+    }
+
+    /// Like [`Parser::parse_ident`], but rejects keywords.
+    fn parse_symbol_name(&mut self) -> Result<Ident, SyntaxError> {
+        let ident = self.parse_ident()?;
+        self.check_symbol_name(ident)
+    }
+
+    fn check_symbol_name(&self, ident: Ident) -> Result<Ident, SyntaxError> {
+        if KEYWORDS.contains(&ident.name.as_str()) {
+            return Err(SyntaxError::new(
+                ident.pos,
+                format!(
+                    "`{}` is a keyword and cannot be used as a symbol name",
+                    ident.name
+                ),
+            ));
+        }
+        Ok(ident)
+    }
+
+    /// Parses a char literal denoting a single byte. Apart from `\xNN`
+    /// escapes, only ASCII characters fit into a byte.
+    fn parse_byte_literal(&mut self) -> Result<u8, SyntaxError> {
+        let start = self.pos();
+        self.bump();
+
+        let byte = match self.peek() {
+            None | Some('\n') => {
+                return Err(SyntaxError::new(
+                    start,
+                    "unterminated char literal".to_string(),
+                ));
+            }
+            Some('\'') => return Err(SyntaxError::new(start, "empty char literal".to_string())),
+            Some('\\') => self.parse_escape()? as u8,
+            Some(c) if c.is_ascii() => {
+                self.bump();
+                c as u8
+            }
+            Some(c) => {
+                return Err(SyntaxError::new(
+                    self.pos(),
+                    format!(
+                        "{:?} does not fit into a single byte, match it with `u[{}]`",
+                        c, c
+                    ),
+                ));
+            }
+        };
+
+        if self.peek() != Some('\'') {
+            return Err(SyntaxError::new(
+                start,
+                "char literal must contain exactly one character".to_string(),
+            ));
+        }
+        self.bump();
+
+        Ok(byte)
+    }
+
+    /// Parses a byte class like `[a-zA-Z_]` or `[^"\n]`. Besides the escapes
+    /// of char literals, `\]`, `\-` and `\^` denote the literal bytes.
+    fn parse_byte_class(&mut self) -> Result<(Vec<RangeInclusive<u8>>, bool), SyntaxError> {
+        let start = self.pos();
+        self.bump();
+
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.bump();
+        }
+
+        let mut ranges = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some('\n') => {
+                    return Err(SyntaxError::new(
+                        start,
+                        "unterminated byte class".to_string(),
+                    ));
+                }
+                Some(']') => {
+                    self.bump();
+                    break;
+                }
+                _ => {}
+            }
+
+            let pos = self.pos();
+            let first = self.parse_class_byte()?;
+            // A `-` right before the closing `]` is a literal byte
+            let last = if self.peek() == Some('-') && self.peek_at(1) != Some(']') {
+                self.bump();
+                self.parse_class_byte()?
+            } else {
+                first
+            };
+
+            if last < first {
+                return Err(SyntaxError::new(
+                    pos,
+                    "byte range is out of order".to_string(),
+                ));
+            }
+            ranges.push(first..=last);
+        }
+
+        if ranges.is_empty() {
+            return Err(SyntaxError::new(start, "empty byte class".to_string()));
+        }
+
+        Ok((ranges, negated))
+    }
+
+    /// Parses the optional `with case_insensitive, not_followed_by = [...]`
+    /// suffix of a trie terminal.
+    fn parse_trie_options(&mut self) -> Result<TrieOptions, SyntaxError> {
+        let mut options = TrieOptions::default();
+
+        self.skip_trivia()?;
+        if !self.peek().is_some_and(is_ident_start) {
+            return Ok(options);
+        }
+        let with = self.parse_ident()?;
+        if with.name != "with" {
+            return Err(SyntaxError::new(
+                with.pos,
+                format!("expected `with` or `;`, found `{}`", with.name),
+            ));
+        }
+
+        let mut seen = Vec::new();
+        loop {
+            let option = self.parse_ident()?;
+            if seen.contains(&option.name) {
+                return Err(SyntaxError::new(
+                    option.pos,
+                    format!("duplicate option `{}`", option.name),
+                ));
+            }
+
+            match option.name.as_str() {
+                "case_insensitive" => options.case_insensitive = true,
+                "not_followed_by" => {
+                    self.expect('=')?;
+                    self.skip_trivia()?;
+                    if self.peek() != Some('[') {
+                        return Err(self.unexpected("a byte class"));
+                    }
+                    let (ranges, negated) = self.parse_byte_class()?;
+                    options.not_followed_by = (0..=u8::MAX)
+                        .filter(|b| ranges.iter().any(|range| range.contains(b)) != negated)
+                        .collect();
+                }
+                _ => {
+                    return Err(SyntaxError::new(
+                        option.pos,
+                        format!(
+                            "unknown option `{}`, expected `case_insensitive` or `not_followed_by`",
+                            option.name
+                        ),
+                    ));
+                }
+            }
+            seen.push(option.name);
+
+            if !self.eat(',')? {
+                return Ok(options);
+            }
+        }
+    }
+
+    /// Parses a `/.../` regex literal and returns the pattern between the
+    /// slashes. `\/` stands for a slash, other escapes are left to the regex
+    /// parser.
+    fn parse_regex_literal(&mut self) -> Result<String, SyntaxError> {
+        let start = self.pos();
+        self.bump();
+
+        let mut pattern = String::new();
+        loop {
+            match self.peek() {
+                None | Some('\n') => {
+                    return Err(SyntaxError::new(start, "unterminated regex".to_string()));
+                }
+                Some('/') => {
+                    self.bump();
+                    break;
+                }
+                Some('\\') if self.peek_at(1) == Some('/') => {
+                    self.bump();
+                    self.bump();
+                    pattern.push('/');
+                }
+                Some('\\') => {
+                    self.bump();
+                    pattern.push('\\');
+                    if let Some(c) = self.peek() {
+                        self.bump();
+                        pattern.push(c);
+                    }
+                }
+                Some(c) => {
+                    self.bump();
+                    pattern.push(c);
+                }
+            }
+        }
+
+        Ok(pattern)
+    }
+
+    /// Parses a Unicode class like `u[\p{Letter}_]` after the `u`. Items are
+    /// any characters, ranges of them, and categories written as `\p{Name}`.
+    /// `\u{...}` escapes denote characters by their code point.
+    fn parse_char_class(&mut self) -> Result<CharClass, SyntaxError> {
+        let start = self.pos();
+        self.bump();
+
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.bump();
+        }
+
+        let mut ranges = Vec::new();
+        let mut categories = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some('\n') => {
+                    return Err(SyntaxError::new(
+                        start,
+                        "unterminated character class".to_string(),
+                    ));
+                }
+                Some(']') => {
+                    self.bump();
+                    break;
+                }
+                Some('\\') if self.peek_at(1) == Some('p') => {
+                    self.bump();
+                    self.bump();
+                    self.expect('{')?;
+                    let name = self.parse_ident()?;
+                    self.expect('}')?;
+
+                    let category = UnicodeCategory::from_name(&name.name).ok_or_else(|| {
+                        SyntaxError::new(
+                            name.pos,
+                            format!("unknown Unicode category `{}`", name.name),
+                        )
+                    })?;
+                    categories.push(category);
+                    continue;
+                }
+                _ => {}
+            }
+
+            let pos = self.pos();
+            let first = self.parse_class_char()?;
+            // A `-` right before the closing `]` is a literal character
+            let last = if self.peek() == Some('-') && self.peek_at(1) != Some(']') {
+                self.bump();
+                self.parse_class_char()?
+            } else {
+                first
+            };
+
+            if last < first {
+                return Err(SyntaxError::new(
+                    pos,
+                    "character range is out of order".to_string(),
+                ));
+            }
+            ranges.push(first..=last);
+        }
+
+        if ranges.is_empty() && categories.is_empty() {
+            return Err(SyntaxError::new(start, "empty character class".to_string()));
+        }
+
+        Ok((ranges, categories, negated))
+    }
+
+    fn parse_class_char(&mut self) -> Result<char, SyntaxError> {
+        match self.peek() {
+            Some('\\') if matches!(self.peek_at(1), Some(']' | '-' | '^')) => {
+                self.bump();
+                let c = self.peek().unwrap_or_default();
+                self.bump();
+                Ok(c)
+            }
+            Some('\\') if self.peek_at(1) == Some('u') => {
+                let pos = self.pos();
+                self.bump();
+                self.bump();
+                self.expect('{')?;
+
+                let mut digits = String::new();
+                while let Some(c) = self.peek().filter(char::is_ascii_hexdigit) {
+                    digits.push(c);
+                    self.bump();
+                }
+                self.expect('}')?;
+
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| SyntaxError::new(pos, "invalid Unicode escape".to_string()))
+            }
+            Some('\\') => self.parse_escape(),
+            Some(c) => {
+                self.bump();
+                Ok(c)
+            }
+            None => Err(self.unexpected("a character")),
+        }
+    }
+
+    fn parse_class_byte(&mut self) -> Result<u8, SyntaxError> {
+        match self.peek() {
+            Some('\\') if matches!(self.peek_at(1), Some(']' | '-' | '^')) => {
+                self.bump();
+                let c = self.peek().unwrap_or_default();
+                self.bump();
+                Ok(c as u8)
+            }
+            Some('\\') => Ok(self.parse_escape()? as u8),
+            Some(c) if c.is_ascii() => {
+                self.bump();
+                Ok(c as u8)
+            }
+            Some(c) => Err(SyntaxError::new(
+                self.pos(),
+                format!("{:?} does not fit into a single byte", c),
+            )),
+            None => Err(self.unexpected("a byte")),
+        }
+    }
+
+    fn parse_string_literal(&mut self) -> Result<Vec<u8>, SyntaxError> {
+        let start = self.pos();
+        if self.peek() != Some('"') {
+            return Err(self.unexpected("a string literal"));
+        }
+        self.bump();
+
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(SyntaxError::new(
+                        start,
+                        "unterminated string literal".to_string(),
+                    ));
+                }
+                Some('"') => {
+                    self.bump();
+                    break;
+                }
+                Some('\\') => {
+                    // `\xNN` escapes denote raw bytes, everything else is UTF-8 encoded
+                    let raw = self.peek_at(1) == Some('x');
+                    let c = self.parse_escape()?;
+                    if raw {
+                        bytes.push(c as u8);
+                    } else {
+                        bytes.extend(c.to_string().as_bytes());
+                    }
+                }
+                Some(c) => {
+                    self.bump();
+                    bytes.extend(c.to_string().as_bytes());
+                }
+            }
+        }
+
+        if bytes.is_empty() {
+            return Err(SyntaxError::new(start, "empty string literal".to_string()));
+        }
+
+        Ok(bytes)
+    }
+
+    fn parse_escape(&mut self) -> Result<char, SyntaxError> {
+        let pos = self.pos();
+        self.bump();
+
+        let c = match self.peek() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('\'') => '\'',
+            Some('"') => '"',
+            Some('x') => {
+                self.bump();
+                let digits: String = [self.peek(), self.peek_at(1)].iter().flatten().collect();
+                let value = u8::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() == 2)
+                    .ok_or_else(|| {
+                        SyntaxError::new(pos, "expected two hex digits after `\\x`".to_string())
+                    })?;
+                self.bump();
+                self.bump();
+                return Ok(value as char);
+            }
+            _ => return Err(SyntaxError::new(pos, "unknown escape sequence".to_string())),
+        };
+        self.bump();
+
+        Ok(c)
+    }
+
+    fn skip_comment(&mut self) -> Result<(), SyntaxError> {
+        let start = self.pos();
+        self.bump();
+        if self.peek() == Some('/') {
+            while let Some(c) = self.peek() {
+                if c == '\n' {
+                    break;
+                }
+                self.bump();
+            }
+        } else {
+            self.bump();
+            loop {
+                match self.peek() {
+                    None => {
+                        return Err(SyntaxError::new(
+                            start,
+                            "unterminated block comment".to_string(),
+                        ));
+                    }
+                    Some('*') if self.peek_at(1) == Some('/') => {
+                        self.bump();
+                        self.bump();
+                        break;
+                    }
+                    Some(_) => self.bump(),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_trivia(&mut self) -> Result<(), SyntaxError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => self.bump(),
+                Some('/') if matches!(self.peek_at(1), Some('/' | '*')) => self.skip_comment()?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn eat(&mut self, c: char) -> Result<bool, SyntaxError> {
+        self.skip_trivia()?;
+        if self.peek() == Some(c) {
+            self.bump();
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SyntaxError> {
+        if self.eat(c)? {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{}`", c)))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> SyntaxError {
+        let found = match self.peek() {
+            Some(c) => format!("`{}`", c),
+            None => "end of file".to_string(),
+        };
+        SyntaxError::new(
+            self.pos(),
+            format!("expected {}, found {}", expected, found),
+        )
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.index + offset).copied()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.index += 1;
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+    }
+
+    fn pos(&self) -> Position {
+        Position::new(self.line, self.column)
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
@@ -1,14 +1,25 @@
 use std::collections::HashSet;
 
+use error::GrammarError;
+use skip::SkipSet;
 use symbols::{
     non_terminal::NonTerminal,
     refs::{NonTerminalRef, SymbolRef, TerminalRef},
-    terminal::{ByteTerminal, TerminalLike},
+    terminal::TerminalLike,
 };
 
 pub mod analysis;
+pub mod ebnf;
+pub mod error;
 pub mod generator;
+pub mod loader;
+pub mod operators;
+pub mod recovery;
+pub mod regex;
+pub mod rewrite;
+pub mod skip;
 pub mod symbols;
+pub mod validation;
 
 pub trait StrRepr {
     fn repr(&self, grammar: &Grammar) -> String;
@@ -53,14 +64,15 @@ impl Word {
         Word(symbols)
     }
 
-    pub fn iter(&self) -> std::slice::Iter<SymbolRef> {
+    pub fn iter(&self) -> std::slice::Iter<'_, SymbolRef> {
         self.0.iter()
     }
 
-    pub fn test_indices(&self, grammar: &Grammar) {
+    pub fn test_indices(&self, grammar: &Grammar) -> Result<(), GrammarError> {
         for symbol in self.0.iter() {
-            symbol.test_index(grammar);
+            symbol.test_index(grammar)?;
         }
+        Ok(())
     }
 
     pub fn as_slice(&self) -> &[SymbolRef] {
@@ -97,9 +109,9 @@ impl Rule {
         self.lhs.deref(grammar)
     }
 
-    pub fn test_indices(&self, grammar: &Grammar) {
-        self.lhs.test_index(grammar);
-        self.rhs.test_indices(grammar);
+    pub fn test_indices(&self, grammar: &Grammar) -> Result<(), GrammarError> {
+        self.lhs.test_index(grammar)?;
+        self.rhs.test_indices(grammar)
     }
 }
 
@@ -113,11 +125,17 @@ impl StrRepr for Rule {
     }
 }
 
+#[derive(Default)]
 pub struct Grammar {
     pub start: Option<NonTerminalRef>,
     pub terminals: Vec<Box<dyn TerminalLike>>,
     pub non_terminals: Vec<NonTerminal>,
     pub rules: Vec<Rule>,
+    /// Whether the generated parser splits the input into tokens first. The
+    /// analysis and the rules then work on whole tokens instead of bytes.
+    pub lexer: bool,
+    /// What the generated parser skips before every terminal.
+    pub skip: SkipSet,
 }
 
 impl Grammar {
@@ -127,39 +145,51 @@ impl Grammar {
             start: None,
             terminals: Vec::new(),
             non_terminals: Vec::new(),
+            lexer: false,
+            skip: SkipSet::new(),
         }
     }
 
-    pub fn add_rule(&mut self, rule: Rule) {
-        rule.test_indices(self);
+    pub fn add_rule(&mut self, rule: Rule) -> Result<(), GrammarError> {
+        rule.test_indices(self)?;
 
         self.rules.push(rule);
+        Ok(())
     }
 
-    pub fn add_terminal(&mut self, terminal: impl TerminalLike + 'static) -> TerminalRef {
-        if self.has_symbol_with_name(&terminal.name()) {
-            panic!("Symbol with name {} already exists", terminal.name());
+    pub fn add_terminal(
+        &mut self,
+        terminal: impl TerminalLike + 'static,
+    ) -> Result<TerminalRef, GrammarError> {
+        if self.has_symbol_with_name(terminal.name()) {
+            return Err(GrammarError::DuplicateSymbol {
+                name: terminal.name().to_string(),
+            });
         }
 
         self.terminals.push(Box::new(terminal));
-        TerminalRef::new(self.terminals.len() - 1)
+        Ok(TerminalRef::new(self.terminals.len() - 1))
     }
 
-    pub fn add_non_terminal(&mut self, non_terminal: NonTerminal) -> NonTerminalRef {
+    pub fn add_non_terminal(
+        &mut self,
+        non_terminal: NonTerminal,
+    ) -> Result<NonTerminalRef, GrammarError> {
         if self.has_symbol_with_name(&non_terminal.name) {
-            panic!("Symbol with name {} already exists", non_terminal.name);
+            return Err(GrammarError::DuplicateSymbol {
+                name: non_terminal.name,
+            });
         }
 
         self.non_terminals.push(non_terminal);
-        NonTerminalRef::new(self.non_terminals.len() - 1)
+        Ok(NonTerminalRef::new(self.non_terminals.len() - 1))
     }
 
-    pub fn set_start(&mut self, start: NonTerminalRef) {
-        if !self.has_non_terminal_with_name(&start.deref(self).name) {
-            panic!("Start symbol {} does not exist", start.deref(self).name);
-        }
+    pub fn set_start(&mut self, start: NonTerminalRef) -> Result<(), GrammarError> {
+        start.test_index(self)?;
 
         self.start = Some(start);
+        Ok(())
     }
 
     pub fn repr(&self) -> String {
@@ -191,8 +221,8 @@ impl Grammar {
         self.start.map(|nt_ref| nt_ref.deref(self))
     }
 
-    pub fn get_terminal(&self, index: usize) -> Option<&Box<dyn TerminalLike>> {
-        self.terminals.get(index)
+    pub fn get_terminal(&self, index: usize) -> Option<&dyn TerminalLike> {
+        self.terminals.get(index).map(|t| t.as_ref())
     }
 
     pub fn get_non_terminal(&self, index: usize) -> Option<&NonTerminal> {
@@ -218,6 +248,18 @@ impl Grammar {
             .collect()
     }
 
+    /// The rules of the non-terminal together with their index into
+    /// [`Grammar::rules`].
+    pub fn iter_rules_for_non_terminal(
+        &self,
+        nt_ref: NonTerminalRef,
+    ) -> impl Iterator<Item = (usize, &Rule)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(move |(_, r)| r.lhs == nt_ref)
+    }
+
     pub fn iter_terminals(&self) -> impl Iterator<Item = (&Box<dyn TerminalLike>, TerminalRef)> {
         self.terminals
             .iter()
@@ -232,11 +274,11 @@ impl Grammar {
             .map(|(i, t)| (t, NonTerminalRef::new(i)))
     }
 
-    pub fn iter_terminal_refs(&self) -> impl Iterator<Item = TerminalRef> {
-        (0..self.terminals.len()).map(|i| TerminalRef::new(i))
+    pub fn iter_terminal_refs(&self) -> impl Iterator<Item = TerminalRef> + use<> {
+        (0..self.terminals.len()).map(TerminalRef::new)
     }
 
-    pub fn iter_non_terminal_refs(&self) -> impl Iterator<Item = NonTerminalRef> {
-        (0..self.non_terminals.len()).map(|i| NonTerminalRef::new(i))
+    pub fn iter_non_terminal_refs(&self) -> impl Iterator<Item = NonTerminalRef> + use<> {
+        (0..self.non_terminals.len()).map(NonTerminalRef::new)
     }
 }
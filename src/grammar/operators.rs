@@ -0,0 +1,73 @@
+//! Non-terminals for expressions with operators of different precedence,
+//! which are awkward to write as LL(1) rules.
+
+use super::{
+    Grammar, Rule, Word,
+    error::GrammarError,
+    symbols::{
+        non_terminal::{Fixity, NonTerminalKind, Operator},
+        refs::{NonTerminalRef, SymbolRef},
+    },
+};
+
+/// The transform of the rules that only describe an operator non-terminal to
+/// the analysis.
+const DESCRIPTIVE_TRANSFORM: &str = "unreachable!(\"parsed by precedence climbing\")";
+
+impl Grammar {
+    /// Turns `nt_ref` into a non-terminal parsed by precedence climbing with
+    /// the given operators. Returns a new non-terminal of the same type for
+    /// the operands, which the caller adds the operand rules to, e.g.
+    /// numbers and parenthesized expressions.
+    ///
+    /// `nt_ref` must not have rules of its own. It gets rules that describe
+    /// the expressions to the analysis without regard to precedence:
+    /// `E -> op E` for each prefix operator, `E -> operand E'`, and
+    /// `E' -> op E | op E' | ε` for each infix and postfix operator.
+    pub fn add_operators(
+        &mut self,
+        nt_ref: NonTerminalRef,
+        operators: Vec<Operator>,
+    ) -> Result<NonTerminalRef, GrammarError> {
+        nt_ref.test_index(self)?;
+        for operator in &operators {
+            operator.symbol.test_index(self)?;
+        }
+
+        let ast_type = nt_ref.deref(self).ast_type.clone();
+        let operand = self.add_helper_non_terminal(nt_ref, ast_type);
+        let tail = self.add_helper_non_terminal(nt_ref, "()".to_string());
+        self.non_terminals[tail.index()].kind = NonTerminalKind::Inlined;
+
+        let expression = SymbolRef::NonTerminal(nt_ref);
+        for operator in &operators {
+            let (lhs, symbols) = match operator.fixity {
+                Fixity::Prefix => (nt_ref, vec![operator.symbol, expression]),
+                Fixity::Postfix => (tail, vec![operator.symbol, SymbolRef::NonTerminal(tail)]),
+                Fixity::Infix(_) => (tail, vec![operator.symbol, expression]),
+            };
+            self.add_rule(Rule::new(
+                lhs,
+                Word::new(symbols),
+                DESCRIPTIVE_TRANSFORM.to_string(),
+            ))?;
+        }
+        self.add_rule(Rule::new(
+            nt_ref,
+            Word::new(vec![
+                SymbolRef::NonTerminal(operand),
+                SymbolRef::NonTerminal(tail),
+            ]),
+            DESCRIPTIVE_TRANSFORM.to_string(),
+        ))?;
+        self.add_rule(Rule::new(
+            tail,
+            Word::new(vec![SymbolRef::Epsilon]),
+            DESCRIPTIVE_TRANSFORM.to_string(),
+        ))?;
+
+        self.non_terminals[nt_ref.index()].kind = NonTerminalKind::Operators { operand, operators };
+
+        Ok(operand)
+    }
+}
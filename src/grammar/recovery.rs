@@ -0,0 +1,55 @@
+//! Recovery from errors in the generated parser, so that it can report more
+//! than the first error and still produce an AST, e.g. for an editor.
+
+use std::collections::HashSet;
+
+use super::{
+    Grammar,
+    analysis::{Analysis, Lookahead, terminal_first},
+    error::GrammarError,
+    symbols::{non_terminal::Recovery, refs::NonTerminalRef},
+};
+
+impl Grammar {
+    /// Lets the generated parser recover from errors in `nt_ref`, see
+    /// [`Recovery`]. A grammar with any recovering non-terminal gets entry
+    /// points that return the errors along with what they parsed instead of
+    /// a `Result`.
+    pub fn set_recovery(
+        &mut self,
+        nt_ref: NonTerminalRef,
+        recovery: Recovery,
+    ) -> Result<(), GrammarError> {
+        nt_ref.test_index(self)?;
+        for t_ref in recovery.sync.iter().flatten() {
+            t_ref.test_index(self)?;
+        }
+
+        self.non_terminals[nt_ref.index()].recovery = Some(recovery);
+        Ok(())
+    }
+
+    /// Whether any non-terminal recovers from errors.
+    pub fn recovers(&self) -> bool {
+        self.non_terminals.iter().any(|nt| nt.recovery.is_some())
+    }
+
+    /// The lookaheads the parser skips to after an error in a recovering
+    /// non-terminal, always including the end of the input.
+    pub fn sync_set(&self, nt_ref: NonTerminalRef, analysis: &Analysis) -> HashSet<Lookahead> {
+        let mut sync = match nt_ref
+            .deref(self)
+            .recovery
+            .as_ref()
+            .and_then(|recovery| recovery.sync.as_ref())
+        {
+            Some(terminals) => terminals
+                .iter()
+                .flat_map(|t_ref| terminal_first(*t_ref, self))
+                .collect(),
+            None => analysis.follow(nt_ref).clone(),
+        };
+        sync.insert(Lookahead::Eof);
+        sync
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque, hash_map::Entry};
+
+use super::Regex;
+
+/// A Thompson NFA, only used on the way to the [`Dfa`].
+#[derive(Default)]
+struct Nfa {
+    states: Vec<NfaState>,
+}
+
+#[derive(Default)]
+struct NfaState {
+    /// Transitions on one byte out of a set.
+    bytes: Vec<(HashSet<u8>, usize)>,
+    epsilon: Vec<usize>,
+}
+
+impl Nfa {
+    fn add_state(&mut self) -> usize {
+        self.states.push(NfaState::default());
+        self.states.len() - 1
+    }
+
+    /// Adds states matching the regex after `start` and returns the state
+    /// where the match ends.
+    fn add(&mut self, regex: &Regex, start: usize) -> usize {
+        match regex {
+            Regex::Empty => start,
+            Regex::Class(class) => {
+                let end = self.add_state();
+                self.states[start].bytes.push((class.clone(), end));
+                end
+            }
+            Regex::Concat(parts) => parts
+                .iter()
+                .fold(start, |state, part| self.add(part, state)),
+            Regex::Alternation(alternatives) => {
+                let end = self.add_state();
+                for alternative in alternatives {
+                    let alternative_start = self.add_state();
+                    self.states[start].epsilon.push(alternative_start);
+                    let alternative_end = self.add(alternative, alternative_start);
+                    self.states[alternative_end].epsilon.push(end);
+                }
+                end
+            }
+            Regex::Repeat { inner, min, max } => {
+                let mut state = start;
+                for _ in 0..*min {
+                    state = self.add(inner, state);
+                }
+
+                match max {
+                    None => {
+                        let repeat = self.add_state();
+                        self.states[state].epsilon.push(repeat);
+                        let inner_end = self.add(inner, repeat);
+                        self.states[inner_end].epsilon.push(repeat);
+                        repeat
+                    }
+                    Some(max) => {
+                        let end = self.add_state();
+                        for _ in *min..*max {
+                            self.states[state].epsilon.push(end);
+                            state = self.add(inner, state);
+                        }
+                        self.states[state].epsilon.push(end);
+                        end
+                    }
+                }
+            }
+        }
+    }
+
+    fn epsilon_closure(&self, states: impl IntoIterator<Item = usize>) -> BTreeSet<usize> {
+        let mut closure = BTreeSet::new();
+        let mut stack: Vec<usize> = states.into_iter().collect();
+
+        while let Some(state) = stack.pop() {
+            if closure.insert(state) {
+                stack.extend(&self.states[state].epsilon);
+            }
+        }
+
+        closure
+    }
+}
+
+/// A minimized deterministic automaton over bytes. State 0 is the start
+/// state, and a missing transition means that the match cannot continue.
+#[derive(Debug, Clone)]
+pub struct Dfa {
+    pub transitions: Vec<Vec<Option<usize>>>,
+    pub accepting: Vec<bool>,
+}
+
+impl Dfa {
+    pub fn new(regex: &Regex) -> Self {
+        let mut nfa = Nfa::default();
+        let start = nfa.add_state();
+        let end = nfa.add(regex, start);
+
+        // Subset construction
+        let mut dfa = Dfa {
+            transitions: Vec::new(),
+            accepting: Vec::new(),
+        };
+        let mut indices = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        let start_set = nfa.epsilon_closure([start]);
+        indices.insert(start_set.clone(), 0);
+        dfa.add_state(start_set.contains(&end));
+        queue.push_back(start_set);
+
+        while let Some(set) = queue.pop_front() {
+            let index = indices[&set];
+            for byte in 0..=u8::MAX {
+                let targets = set
+                    .iter()
+                    .flat_map(|state| &nfa.states[*state].bytes)
+                    .filter(|(class, _)| class.contains(&byte))
+                    .map(|(_, target)| *target)
+                    .collect::<Vec<_>>();
+                if targets.is_empty() {
+                    continue;
+                }
+
+                let target_set = nfa.epsilon_closure(targets);
+                let target = match indices.get(&target_set) {
+                    Some(target) => *target,
+                    None => {
+                        let target = dfa.add_state(target_set.contains(&end));
+                        indices.insert(target_set.clone(), target);
+                        queue.push_back(target_set);
+                        target
+                    }
+                };
+                dfa.transitions[index][byte as usize] = Some(target);
+            }
+        }
+
+        dfa.minimize()
+    }
+
+    fn add_state(&mut self, accepting: bool) -> usize {
+        self.transitions.push(vec![None; 256]);
+        self.accepting.push(accepting);
+        self.transitions.len() - 1
+    }
+
+    /// Merges equivalent states by partition refinement, after dropping
+    /// transitions into states from which no match is possible.
+    fn minimize(mut self) -> Self {
+        let live = self.live_states();
+        for transitions in &mut self.transitions {
+            for target in transitions.iter_mut() {
+                if target.is_some_and(|target| !live.contains(&target)) {
+                    *target = None;
+                }
+            }
+        }
+
+        let mut classes: Vec<usize> = self.accepting.iter().map(|a| *a as usize).collect();
+        let mut class_count = 0;
+        loop {
+            let mut signatures = HashMap::new();
+            let refined: Vec<usize> = (0..self.transitions.len())
+                .map(|state| {
+                    let signature = (
+                        classes[state],
+                        self.transitions[state]
+                            .iter()
+                            .map(|target| target.map(|target| classes[target]))
+                            .collect::<Vec<_>>(),
+                    );
+                    let next = signatures.len();
+                    *signatures.entry(signature).or_insert(next)
+                })
+                .collect();
+
+            classes = refined;
+            if signatures.len() == class_count {
+                break;
+            }
+            class_count = signatures.len();
+        }
+
+        // Number the merged states in the order they are reached from the
+        // start state, which also drops unreachable ones
+        let mut numbers = HashMap::from([(classes[0], 0)]);
+        let mut representatives = vec![0];
+        let mut index = 0;
+        while index < representatives.len() {
+            let state = representatives[index];
+            for target in self.transitions[state].iter().flatten() {
+                if let Entry::Vacant(entry) = numbers.entry(classes[*target]) {
+                    entry.insert(representatives.len());
+                    representatives.push(*target);
+                }
+            }
+            index += 1;
+        }
+
+        Dfa {
+            transitions: representatives
+                .iter()
+                .map(|state| {
+                    self.transitions[*state]
+                        .iter()
+                        .map(|target| target.map(|target| numbers[&classes[target]]))
+                        .collect()
+                })
+                .collect(),
+            accepting: representatives
+                .iter()
+                .map(|state| self.accepting[*state])
+                .collect(),
+        }
+    }
+
+    /// The states from which an accepting state can be reached.
+    fn live_states(&self) -> HashSet<usize> {
+        let mut live: HashSet<usize> = (0..self.accepting.len())
+            .filter(|state| self.accepting[*state])
+            .collect();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (state, transitions) in self.transitions.iter().enumerate() {
+                if !live.contains(&state)
+                    && transitions
+                        .iter()
+                        .flatten()
+                        .any(|target| live.contains(target))
+                {
+                    live.insert(state);
+                    changed = true;
+                }
+            }
+        }
+
+        live
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Whether the automaton matches any input at all.
+    pub fn matches_anything(&self) -> bool {
+        self.accepting.iter().any(|accepting| *accepting)
+    }
+
+    /// The bytes a match can start with.
+    pub fn first_bytes(&self) -> HashSet<u8> {
+        (0..=u8::MAX)
+            .filter(|byte| self.transitions[0][*byte as usize].is_some())
+            .collect()
+    }
+
+    /// The transitions of a state, with the bytes leading to the same state
+    /// grouped together, ordered by target.
+    pub fn grouped_transitions(&self, state: usize) -> Vec<(HashSet<u8>, usize)> {
+        let mut groups: Vec<(HashSet<u8>, usize)> = Vec::new();
+        for (byte, target) in self.transitions[state].iter().enumerate() {
+            let Some(target) = *target else {
+                continue;
+            };
+            match groups.iter_mut().find(|(_, t)| *t == target) {
+                Some((bytes, _)) => {
+                    bytes.insert(byte as u8);
+                }
+                None => groups.push((HashSet::from([byte as u8]), target)),
+            }
+        }
+        groups.sort_by_key(|(_, target)| *target);
+        groups
+    }
+
+    /// One of the shortest inputs the automaton accepts, preferring smaller
+    /// bytes. Empty if it accepts nothing.
+    pub fn shortest_match(&self) -> Vec<u8> {
+        let mut previous: Vec<Option<(usize, u8)>> = vec![None; self.state_count()];
+        let mut visited = vec![false; self.state_count()];
+        let mut queue = VecDeque::from([0]);
+        visited[0] = true;
+
+        while let Some(state) = queue.pop_front() {
+            if self.accepting[state] {
+                let mut word = Vec::new();
+                let mut current = state;
+                while let Some((from, byte)) = previous[current] {
+                    word.push(byte);
+                    current = from;
+                }
+                word.reverse();
+                return word;
+            }
+
+            for (byte, target) in self.transitions[state].iter().enumerate() {
+                if let Some(target) = *target
+                    && !visited[target]
+                {
+                    visited[target] = true;
+                    previous[target] = Some((state, byte as u8));
+                    queue.push_back(target);
+                }
+            }
+        }
+
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dfa(pattern: &str) -> Dfa {
+        Dfa::new(&Regex::parse(pattern).unwrap())
+    }
+
+    /// Whether the automaton accepts the whole input.
+    fn accepts(dfa: &Dfa, input: &[u8]) -> bool {
+        let mut state = 0;
+        for byte in input {
+            match dfa.transitions[state][*byte as usize] {
+                Some(next) => state = next,
+                None => return false,
+            }
+        }
+        dfa.accepting[state]
+    }
+
+    #[test]
+    fn matches_whole_inputs() {
+        let number = dfa("-?\\d+(\\.\\d+)?");
+        for input in ["0", "-12", "3.25"] {
+            assert!(accepts(&number, input.as_bytes()), "{}", input);
+        }
+        for input in ["", "-", "1.", ".5", "1.2.3"] {
+            assert!(!accepts(&number, input.as_bytes()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn minimizes_equivalent_states() {
+        assert_eq!(dfa("a*|a+").state_count(), 1);
+        assert_eq!(
+            dfa("(a|b)*abb").state_count(),
+            dfa("[ab]*abb").state_count()
+        );
+        assert_eq!(dfa("(a|b)*abb").state_count(), 4);
+        assert_eq!(dfa("a{2,4}").state_count(), 5);
+    }
+
+    #[test]
+    fn drops_states_that_cannot_accept() {
+        let dfa = dfa("a[^\\x00-\\xff]|b");
+        assert_eq!(dfa.first_bytes(), HashSet::from([b'b']));
+        assert_eq!(dfa.state_count(), 2);
+    }
+
+    #[test]
+    fn finds_shortest_match() {
+        assert_eq!(dfa("[b-z]+|xyz|a{3}").shortest_match(), b"b");
+        assert_eq!(dfa("\"[^\"]*\"").shortest_match(), b"\"\"");
+        assert!(!dfa("[^\\x00-\\xff]").matches_anything());
+    }
+}
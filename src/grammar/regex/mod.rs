@@ -0,0 +1,47 @@
+//! Regular expressions over bytes for [`RegexTerminal`]s. They are compiled
+//! to minimized DFAs when the terminal is created, so the generated parser
+//! needs no regex engine at runtime.
+//!
+//! The syntax is a subset of the usual one:
+//!
+//! - literal characters, with non-ASCII ones matching their UTF-8 bytes,
+//! - `.` for any byte but `\n`,
+//! - classes like `[a-z_]` and `[^"]`,
+//! - `\d`, `\w`, `\s` and their negations `\D`, `\W`, `\S`, also in classes,
+//! - the escapes `\n`, `\r`, `\t`, `\0` and `\xNN`, and a backslash before any
+//!   other ASCII punctuation for the character itself,
+//! - `(...)` groups, `|` alternatives,
+//! - the repetitions `*`, `+`, `?`, `{n}`, `{n,}` and `{n,m}`.
+//!
+//! [`RegexTerminal`]: super::symbols::terminal::RegexTerminal
+
+use std::collections::HashSet;
+
+pub mod dfa;
+pub mod parser;
+
+/// The parsed form of a regular expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Regex {
+    /// Matches the empty string.
+    Empty,
+    /// Matches one byte out of the set.
+    Class(HashSet<u8>),
+    Concat(Vec<Regex>),
+    Alternation(Vec<Regex>),
+    /// Matches `inner` at least `min` and at most `max` times, or any number
+    /// of times above `min` if `max` is `None`.
+    Repeat {
+        inner: Box<Regex>,
+        min: u32,
+        max: Option<u32>,
+    },
+}
+
+impl Regex {
+    /// Parses a regular expression. The error describes the problem and
+    /// where in the pattern it is.
+    pub fn parse(pattern: &str) -> Result<Regex, String> {
+        parser::Parser::new(pattern).parse()
+    }
+}
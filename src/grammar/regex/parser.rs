@@ -0,0 +1,360 @@
+use std::collections::HashSet;
+
+use super::Regex;
+
+/// Repetition counts above this would blow up the automaton.
+const MAX_REPEAT: u32 = 1000;
+
+/// A recursive descent parser for the regex syntax described in
+/// [`crate::grammar::regex`].
+pub struct Parser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl Parser {
+    pub fn new(pattern: &str) -> Self {
+        Parser {
+            chars: pattern.chars().collect(),
+            index: 0,
+        }
+    }
+
+    pub fn parse(mut self) -> Result<Regex, String> {
+        let regex = self.parse_alternation()?;
+        match self.peek() {
+            None => Ok(regex),
+            Some(')') => Err(self.error("unmatched `)`")),
+            Some(c) => Err(self.error(&format!("unexpected `{}`", c))),
+        }
+    }
+
+    fn parse_alternation(&mut self) -> Result<Regex, String> {
+        let mut alternatives = vec![self.parse_concat()?];
+        while self.eat('|') {
+            alternatives.push(self.parse_concat()?);
+        }
+
+        Ok(if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            Regex::Alternation(alternatives)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Regex, String> {
+        let mut parts = Vec::new();
+        while !matches!(self.peek(), None | Some('|' | ')')) {
+            parts.push(self.parse_repeat()?);
+        }
+
+        Ok(match parts.len() {
+            0 => Regex::Empty,
+            1 => parts.remove(0),
+            _ => Regex::Concat(parts),
+        })
+    }
+
+    fn parse_repeat(&mut self) -> Result<Regex, String> {
+        let mut regex = self.parse_atom()?;
+
+        loop {
+            let (min, max) = match self.peek() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                Some('{') => {
+                    self.bump();
+                    let min = self.parse_count()?;
+                    let max = if self.eat(',') {
+                        if self.peek() == Some('}') {
+                            None
+                        } else {
+                            Some(self.parse_count()?)
+                        }
+                    } else {
+                        Some(min)
+                    };
+                    if self.peek() != Some('}') {
+                        return Err(self.error("expected `}`"));
+                    }
+                    if max.is_some_and(|max| max < min) {
+                        return Err(self.error("repetition range is out of order"));
+                    }
+                    (min, max)
+                }
+                _ => return Ok(regex),
+            };
+            self.bump();
+
+            regex = Regex::Repeat {
+                inner: Box::new(regex),
+                min,
+                max,
+            };
+        }
+    }
+
+    fn parse_count(&mut self) -> Result<u32, String> {
+        let mut digits = String::new();
+        while let Some(c) = self.peek().filter(char::is_ascii_digit) {
+            digits.push(c);
+            self.bump();
+        }
+
+        if digits.is_empty() {
+            return Err(self.error("expected a repetition count"));
+        }
+        match digits.parse() {
+            Ok(count) if count <= MAX_REPEAT => Ok(count),
+            _ => Err(self.error(&format!("repetition count is larger than {}", MAX_REPEAT))),
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Regex, String> {
+        let Some(c) = self.peek() else {
+            return Err(self.error("expected an expression"));
+        };
+
+        match c {
+            '(' => {
+                self.bump();
+                let regex = self.parse_alternation()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected `)`"));
+                }
+                Ok(regex)
+            }
+            '[' => Ok(Regex::Class(self.parse_class()?)),
+            '.' => {
+                self.bump();
+                Ok(Regex::Class(
+                    (0..=u8::MAX).filter(|b| *b != b'\n').collect(),
+                ))
+            }
+            '\\' => match self.parse_escape()? {
+                Escape::Byte(byte) => Ok(Regex::Class(HashSet::from([byte]))),
+                Escape::Class(class) => Ok(Regex::Class(class)),
+            },
+            '*' | '+' | '?' | '{' => Err(self.error(&format!("`{}` must follow an expression", c))),
+            _ => {
+                self.bump();
+                let mut buf = [0; 4];
+                let mut bytes = c
+                    .encode_utf8(&mut buf)
+                    .bytes()
+                    .map(|byte| Regex::Class(HashSet::from([byte])))
+                    .collect::<Vec<_>>();
+                Ok(if bytes.len() == 1 {
+                    bytes.remove(0)
+                } else {
+                    Regex::Concat(bytes)
+                })
+            }
+        }
+    }
+
+    /// Parses a class like `[a-z_]` or `[^"\n]`.
+    fn parse_class(&mut self) -> Result<HashSet<u8>, String> {
+        self.bump();
+        let negated = self.eat('^');
+
+        let mut class = HashSet::new();
+        // A `]` right after the opening bracket is a literal byte
+        let mut first = true;
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated class")),
+                Some(']') if !first => {
+                    self.bump();
+                    break;
+                }
+                _ => {}
+            }
+            first = false;
+
+            let start = match self.parse_class_item()? {
+                Escape::Byte(byte) => byte,
+                Escape::Class(set) => {
+                    class.extend(set);
+                    continue;
+                }
+            };
+
+            // A `-` right before the closing `]` is a literal byte
+            if self.peek() == Some('-') && !matches!(self.peek_at(1), None | Some(']')) {
+                self.bump();
+                let end = match self.parse_class_item()? {
+                    Escape::Byte(byte) => byte,
+                    Escape::Class(_) => return Err(self.error("a class cannot end a range")),
+                };
+                if end < start {
+                    return Err(self.error("class range is out of order"));
+                }
+                class.extend(start..=end);
+            } else {
+                class.insert(start);
+            }
+        }
+
+        if negated {
+            class = (0..=u8::MAX).filter(|b| !class.contains(b)).collect();
+        }
+        Ok(class)
+    }
+
+    fn parse_class_item(&mut self) -> Result<Escape, String> {
+        match self.peek() {
+            Some('\\') => self.parse_escape(),
+            Some(c) if c.is_ascii() => {
+                self.bump();
+                Ok(Escape::Byte(c as u8))
+            }
+            _ => Err(self.error("classes can only contain ASCII characters")),
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<Escape, String> {
+        self.bump();
+        let Some(c) = self.peek() else {
+            return Err(self.error("expected an escape sequence"));
+        };
+        self.bump();
+
+        let class = |bytes: &dyn Fn(&u8) -> bool, negated: bool| {
+            Escape::Class((0..=u8::MAX).filter(|b| bytes(b) != negated).collect())
+        };
+        let word = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_';
+        let space = |b: &u8| matches!(b, b' ' | b'\t' | b'\n' | b'\r' | b'\x0b' | b'\x0c');
+
+        Ok(match c {
+            'n' => Escape::Byte(b'\n'),
+            'r' => Escape::Byte(b'\r'),
+            't' => Escape::Byte(b'\t'),
+            '0' => Escape::Byte(0),
+            'x' => {
+                let digits: String = [self.peek(), self.peek_at(1)].iter().flatten().collect();
+                let byte = u8::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() == 2)
+                    .ok_or_else(|| self.error("expected two hex digits after `\\x`"))?;
+                self.bump();
+                self.bump();
+                Escape::Byte(byte)
+            }
+            'd' | 'D' => class(&u8::is_ascii_digit, c == 'D'),
+            'w' | 'W' => class(&word, c == 'W'),
+            's' | 'S' => class(&space, c == 'S'),
+            _ if c.is_ascii_punctuation() => Escape::Byte(c as u8),
+            _ => return Err(self.error(&format!("unknown escape sequence `\\{}`", c))),
+        })
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.index + offset).copied()
+    }
+
+    fn bump(&mut self) {
+        self.index += 1;
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at offset {}", message, self.index)
+    }
+}
+
+/// What an escape sequence stands for.
+enum Escape {
+    Byte(u8),
+    Class(HashSet<u8>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn byte(b: u8) -> Regex {
+        Regex::Class(HashSet::from([b]))
+    }
+
+    #[test]
+    fn parses_concatenation_and_alternation() {
+        assert_eq!(
+            Regex::parse("ab|c").unwrap(),
+            Regex::Alternation(vec![
+                Regex::Concat(vec![byte(b'a'), byte(b'b')]),
+                byte(b'c')
+            ])
+        );
+        assert_eq!(Regex::parse("").unwrap(), Regex::Empty);
+    }
+
+    #[test]
+    fn parses_repetitions() {
+        let repeat = |min, max| Regex::Repeat {
+            inner: Box::new(byte(b'a')),
+            min,
+            max,
+        };
+
+        assert_eq!(Regex::parse("a*").unwrap(), repeat(0, None));
+        assert_eq!(Regex::parse("a+").unwrap(), repeat(1, None));
+        assert_eq!(Regex::parse("a?").unwrap(), repeat(0, Some(1)));
+        assert_eq!(Regex::parse("a{3}").unwrap(), repeat(3, Some(3)));
+        assert_eq!(Regex::parse("a{2,}").unwrap(), repeat(2, None));
+        assert_eq!(Regex::parse("a{2,5}").unwrap(), repeat(2, Some(5)));
+    }
+
+    #[test]
+    fn parses_classes_and_escapes() {
+        assert_eq!(
+            Regex::parse("[a-c_]").unwrap(),
+            Regex::Class(HashSet::from([b'a', b'b', b'c', b'_']))
+        );
+        assert_eq!(
+            Regex::parse("\\d").unwrap(),
+            Regex::Class((b'0'..=b'9').collect())
+        );
+        assert_eq!(
+            Regex::parse("[^\\w]").unwrap(),
+            Regex::parse("\\W").unwrap()
+        );
+        assert_eq!(Regex::parse("\\x41").unwrap(), byte(b'A'));
+        assert_eq!(Regex::parse("\\.").unwrap(), byte(b'.'));
+        assert_eq!(
+            Regex::parse("é").unwrap(),
+            Regex::Concat(vec![byte(0xc3), byte(0xa9)])
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_offset() {
+        assert_eq!(Regex::parse("(a").unwrap_err(), "expected `)` at offset 2");
+        assert_eq!(Regex::parse("a)").unwrap_err(), "unmatched `)` at offset 1");
+        assert_eq!(
+            Regex::parse("*a").unwrap_err(),
+            "`*` must follow an expression at offset 0"
+        );
+        assert_eq!(
+            Regex::parse("a{3,2}").unwrap_err(),
+            "repetition range is out of order at offset 5"
+        );
+        assert_eq!(
+            Regex::parse("\\q").unwrap_err(),
+            "unknown escape sequence `\\q` at offset 2"
+        );
+    }
+}
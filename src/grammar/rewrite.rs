@@ -0,0 +1,493 @@
+//! Rewrites of a grammar that keep the language it accepts and the results
+//! its rules produce, but make it easier to generate an LL(1) parser for.
+
+use std::{collections::BTreeSet, ops::Range};
+
+use super::{
+    Grammar, Rule, Word,
+    analysis::Analysis,
+    error::GrammarError,
+    symbols::{
+        non_terminal::NonTerminal,
+        refs::{NonTerminalRef, SymbolRef},
+    },
+};
+
+impl Grammar {
+    /// Rewrites left-recursive rules into right-recursive ones, so that a
+    /// parser can be generated for grammars like `Expr -> Expr + Term | Term`.
+    ///
+    /// Indirect left recursion is first turned into direct left recursion by
+    /// substituting the rules of the non-terminals on the cycle into each
+    /// other. Direct left recursion `A -> A a | b` then becomes `A -> b A'`
+    /// and `A' -> a A' | ε`. `A'` evaluates to a function that applies the
+    /// transforms of the `a` rules from left to right, so every transform
+    /// sees the same results and spans as with the original rules. This
+    /// requires the AST types of the rewritten non-terminals to be `'static`.
+    ///
+    /// Returns, for each rule of the rewritten grammar, the index of the rule
+    /// it was derived from. If left recursion remains, e.g. because it is
+    /// hidden behind a nullable symbol, the grammar is left unchanged and
+    /// [`GrammarError::LeftRecursive`] is returned.
+    pub fn eliminate_left_recursion(&mut self) -> Result<Vec<usize>, GrammarError> {
+        // Checked once, so that the rewrite can dereference every symbol
+        for rule in &self.rules {
+            rule.test_indices(self)?;
+        }
+
+        let cycles = Analysis::new(self).left_recursion(self);
+        let order: BTreeSet<_> = cycles
+            .iter()
+            .flat_map(|cycle| &cycle.rules)
+            .map(|rule| self.rules[rule.index].lhs.index())
+            .collect();
+        let order: Vec<_> = order.into_iter().map(NonTerminalRef::new).collect();
+
+        let original_rules = self.rules.clone();
+        let original_non_terminals = self.non_terminals.len();
+
+        let mut rules: Vec<_> = self.rules.drain(..).zip(0..).collect();
+        for (i, nt_ref) in order.iter().enumerate() {
+            for earlier in &order[..i] {
+                rules = self.substitute_leading(rules, *nt_ref, *earlier);
+            }
+            rules = self.eliminate_direct_left_recursion(rules, *nt_ref);
+        }
+
+        let origins = rules.iter().map(|(_, origin)| *origin).collect();
+        self.rules = rules.into_iter().map(|(rule, _)| rule).collect();
+
+        let cycles = Analysis::new(self).left_recursion(self);
+        if !cycles.is_empty() {
+            self.rules = original_rules;
+            self.non_terminals.truncate(original_non_terminals);
+            return Err(GrammarError::LeftRecursive { cycles });
+        }
+
+        Ok(origins)
+    }
+
+    /// Replaces every rule `nt -> inner γ` by `nt -> δ γ` for each rule
+    /// `inner -> δ`.
+    fn substitute_leading(
+        &self,
+        rules: Vec<(Rule, usize)>,
+        nt_ref: NonTerminalRef,
+        inner: NonTerminalRef,
+    ) -> Vec<(Rule, usize)> {
+        let inner_rules: Vec<_> = rules
+            .iter()
+            .filter(|(rule, _)| rule.lhs == inner)
+            .map(|(rule, _)| rule.clone())
+            .collect();
+        let inner_type = &inner.deref(self).ast_type;
+
+        let mut result = Vec::new();
+        for (rule, origin) in rules {
+            if rule.lhs != nt_ref || leading_non_terminal(&rule.rhs) != Some(inner) {
+                result.push((rule, origin));
+                continue;
+            }
+
+            let gamma: Vec<_> = symbols(&rule.rhs).skip(1).collect();
+            for inner_rule in &inner_rules {
+                let delta: Vec<_> = symbols(&inner_rule.rhs).collect();
+                let (d, g) = (delta.len(), gamma.len());
+
+                // The results of δ are evaluated by the inner transform, the
+                // ones of γ move back to where the outer transform expects
+                // them. The same goes for their spans.
+                let transform = format!(
+                    "{{ let __outer = ({}, {}); let (res1, span1): ({}, Span) = {{ let span = {}; ({{ {} }}, span) }}; let ({}, {}) = __outer; {} }}",
+                    results(d + 1..d + g + 1),
+                    spans(d + 1..d + g + 1),
+                    inner_type,
+                    span_to(d),
+                    inner_rule.transform,
+                    results(2..g + 2),
+                    spans(2..g + 2),
+                    rule.transform
+                );
+
+                result.push((
+                    Rule::new(nt_ref, word([delta, gamma.clone()].concat()), transform),
+                    origin,
+                ));
+            }
+        }
+
+        result
+    }
+
+    /// Rewrites `A -> A α | β` into `A -> β A'` and `A' -> α A' | ε`.
+    fn eliminate_direct_left_recursion(
+        &mut self,
+        rules: Vec<(Rule, usize)>,
+        nt_ref: NonTerminalRef,
+    ) -> Vec<(Rule, usize)> {
+        let Some(first_origin) = rules
+            .iter()
+            .find(|(rule, _)| rule.lhs == nt_ref && leading_non_terminal(&rule.rhs) == Some(nt_ref))
+            .map(|(_, origin)| *origin)
+        else {
+            return rules;
+        };
+
+        let ast_type = nt_ref.deref(self).ast_type.clone();
+        let fold_type = format!("Box<dyn FnOnce({}, Span) -> {}>", ast_type, ast_type);
+        let tail = self.add_helper_non_terminal(nt_ref, fold_type.clone());
+
+        let mut result = Vec::new();
+        let mut tail_rules = Vec::new();
+
+        for (rule, origin) in rules {
+            if rule.lhs != nt_ref {
+                result.push((rule, origin));
+            } else if leading_non_terminal(&rule.rhs) == Some(nt_ref) {
+                let alpha: Vec<_> = symbols(&rule.rhs).skip(1).collect();
+                let k = alpha.len();
+
+                // Receives the result so far as `res1` and hands the result
+                // of the transform on to the rest of the suffixes, together
+                // with the span from the start of `res1` to the end of α
+                let transform = format!(
+                    "{{ let __suffix = {}; let __suffix_spans = {}; let __end = {}; let __rest = res{}; Box::new(move |__acc: {}, __acc_span: Span| {{ let res1 = __acc; let span1 = __acc_span; let {} = __suffix; let {} = __suffix_spans; let span = Span {{ start: span1.start, end: __end }}; __rest({}, span) }}) as {} }}",
+                    results(1..k + 1),
+                    spans(1..k + 1),
+                    end_of(k),
+                    k + 1,
+                    ast_type,
+                    results(2..k + 2),
+                    spans(2..k + 2),
+                    rule.transform,
+                    fold_type
+                );
+
+                let symbols = [alpha, vec![SymbolRef::NonTerminal(tail)]].concat();
+                tail_rules.push((Rule::new(tail, word(symbols), transform), origin));
+            } else {
+                let beta: Vec<_> = symbols(&rule.rhs).collect();
+                let transform = format!(
+                    "{{ let __fold = res{}; let span = {}; __fold({}, span) }}",
+                    beta.len() + 1,
+                    span_to(beta.len()),
+                    rule.transform
+                );
+
+                let symbols = [beta, vec![SymbolRef::NonTerminal(tail)]].concat();
+                result.push((Rule::new(nt_ref, word(symbols), transform), origin));
+            }
+        }
+
+        tail_rules.push((
+            Rule::new(
+                tail,
+                Word::new(vec![SymbolRef::Epsilon]),
+                format!(
+                    "Box::new(|__acc: {}, _: Span| __acc) as {}",
+                    ast_type, fold_type
+                ),
+            ),
+            first_origin,
+        ));
+        result.extend(tail_rules);
+
+        result
+    }
+}
+
+impl Grammar {
+    /// Factors common prefixes out of the rules of each non-terminal, so that
+    /// a parser can be generated for grammars like `S -> a b | a c`.
+    ///
+    /// Rules `A -> p s1 | p s2` with a longest common prefix `p` become
+    /// `A -> p A'` and `A' -> s1 | s2`. `A'` evaluates to a function that
+    /// takes the results of `p` and applies the transform of the original
+    /// rule to them and the results of the suffix. This requires the result
+    /// types of the symbols in `p` and the AST type of `A` to be `'static`.
+    ///
+    /// Returns, for each rule of the rewritten grammar, the index of the rule
+    /// it was derived from.
+    pub fn left_factor(&mut self) -> Result<Vec<usize>, GrammarError> {
+        // Checked once, so that the rewrite can dereference every symbol
+        for rule in &self.rules {
+            rule.test_indices(self)?;
+        }
+
+        let mut rules: Vec<_> = self.rules.drain(..).zip(0..).collect();
+        let mut stack: Vec<_> = (0..self.non_terminals.len())
+            .rev()
+            .map(NonTerminalRef::new)
+            .collect();
+
+        while let Some(nt_ref) = stack.pop() {
+            let Some(group) = common_prefix_group(&rules, nt_ref) else {
+                continue;
+            };
+
+            let helper;
+            (rules, helper) = self.factor_group(rules, nt_ref, &group);
+            // Both may have further groups, also within the suffixes
+            stack.push(helper);
+            stack.push(nt_ref);
+        }
+
+        let origins = rules.iter().map(|(_, origin)| *origin).collect();
+        self.rules = rules.into_iter().map(|(rule, _)| rule).collect();
+
+        Ok(origins)
+    }
+
+    /// Rewrites the rules of `nt_ref` at the indices in `group`, which share
+    /// a common prefix, returning the new rules and the new helper
+    /// non-terminal.
+    fn factor_group(
+        &mut self,
+        rules: Vec<(Rule, usize)>,
+        nt_ref: NonTerminalRef,
+        group: &[usize],
+    ) -> (Vec<(Rule, usize)>, NonTerminalRef) {
+        let words: Vec<Vec<_>> = group
+            .iter()
+            .map(|i| symbols(&rules[*i].0.rhs).collect())
+            .collect();
+        let p = (1..)
+            .take_while(|len| {
+                words
+                    .iter()
+                    .all(|word| word.len() >= *len && word[len - 1] == words[0][len - 1])
+            })
+            .last()
+            .unwrap_or(1);
+        let prefix = words[0][..p].to_vec();
+
+        let ast_type = nt_ref.deref(self).ast_type.clone();
+        let prefix_type = format!(
+            "({})",
+            prefix
+                .iter()
+                .map(|symbol| format!("{},", self.result_type(*symbol)))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        let prefix_spans_type = format!("({})", "Span, ".repeat(p).trim_end());
+        let rebuild_type = format!(
+            "Box<dyn FnOnce({}, {}, Span) -> {}>",
+            prefix_type, prefix_spans_type, ast_type
+        );
+        let helper = self.add_helper_non_terminal(nt_ref, rebuild_type.clone());
+
+        let mut result = Vec::new();
+        let mut helper_rules = Vec::new();
+
+        for (i, (rule, origin)) in rules.into_iter().enumerate() {
+            let Some(g) = group.iter().position(|index| *index == i) else {
+                result.push((rule, origin));
+                continue;
+            };
+
+            if g == 0 {
+                // Hands the results of the prefix and their spans to the
+                // helper, along with the span of the whole rule
+                let transform = format!(
+                    "{{ let __rebuild = res{}; __rebuild({}, {}, span) }}",
+                    p + 1,
+                    results(1..p + 1),
+                    spans(1..p + 1)
+                );
+                let symbols = [prefix.clone(), vec![SymbolRef::NonTerminal(helper)]].concat();
+                result.push((Rule::new(nt_ref, word(symbols), transform), origin));
+            }
+
+            // Receives the results of the prefix and puts the results of the
+            // suffix after them, where the transform expects them
+            let suffix = words[g][p..].to_vec();
+            let transform = format!(
+                "{{ let __suffix = {}; let __suffix_spans = {}; Box::new(move |__prefix: {}, __prefix_spans: {}, __span: Span| {{ let {} = __prefix; let {} = __prefix_spans; let {} = __suffix; let {} = __suffix_spans; let span = __span; {} }}) as {} }}",
+                results(1..suffix.len() + 1),
+                spans(1..suffix.len() + 1),
+                prefix_type,
+                prefix_spans_type,
+                results(1..p + 1),
+                spans(1..p + 1),
+                results(p + 1..p + suffix.len() + 1),
+                spans(p + 1..p + suffix.len() + 1),
+                rule.transform,
+                rebuild_type
+            );
+            helper_rules.push((Rule::new(helper, word(suffix), transform), origin));
+        }
+
+        result.extend(helper_rules);
+        (result, helper)
+    }
+
+    /// Adds a non-terminal that helps implementing the rules of `nt_ref`,
+    /// named after it with enough `'` appended to make the name unique. Its
+    /// errors are reported as errors in `nt_ref`.
+    pub(super) fn add_helper_non_terminal(
+        &mut self,
+        nt_ref: NonTerminalRef,
+        ast_type: String,
+    ) -> NonTerminalRef {
+        let nt = nt_ref.deref(self);
+        let mut name = format!("{}'", nt.name);
+        while self.has_symbol_with_name(&name) {
+            name.push('\'');
+        }
+        let mut helper = NonTerminal::new(name, ast_type);
+        helper.description = Some(nt.describe().to_string());
+
+        // The name is unique, so the non-terminal can be pushed directly
+        self.non_terminals.push(helper);
+        NonTerminalRef::new(self.non_terminals.len() - 1)
+    }
+
+    /// The type of the result of a symbol.
+    pub(super) fn result_type(&self, symbol: SymbolRef) -> String {
+        match symbol {
+            SymbolRef::Terminal(t_ref) => t_ref.deref(self).result_type(),
+            SymbolRef::NonTerminal(nt_ref) => nt_ref.deref(self).ast_type.clone(),
+            SymbolRef::Epsilon => "()".to_string(),
+        }
+    }
+}
+
+/// The indices of the first set of at least two rules of `nt_ref` that start
+/// with the same symbol.
+fn common_prefix_group(rules: &[(Rule, usize)], nt_ref: NonTerminalRef) -> Option<Vec<usize>> {
+    let leading = |i: usize| -> Option<SymbolRef> {
+        let (rule, _) = &rules[i];
+        if rule.lhs == nt_ref {
+            symbols(&rule.rhs).next()
+        } else {
+            None
+        }
+    };
+
+    (0..rules.len()).find_map(|i| {
+        let symbol = leading(i)?;
+        let group: Vec<_> = (i..rules.len())
+            .filter(|j| leading(*j) == Some(symbol))
+            .collect();
+        (group.len() > 1).then_some(group)
+    })
+}
+
+/// The symbols of the word that produce a result, i.e. all but epsilon.
+fn symbols(word: &Word) -> impl Iterator<Item = SymbolRef> {
+    word.iter().copied().filter(|symbol| !symbol.is_epsilon())
+}
+
+fn leading_non_terminal(word: &Word) -> Option<NonTerminalRef> {
+    match symbols(word).next() {
+        Some(SymbolRef::NonTerminal(nt_ref)) => Some(nt_ref),
+        _ => None,
+    }
+}
+
+/// A word of the symbols, or epsilon if there are none.
+pub(super) fn word(symbols: Vec<SymbolRef>) -> Word {
+    if symbols.is_empty() {
+        Word::new(vec![SymbolRef::Epsilon])
+    } else {
+        Word::new(symbols)
+    }
+}
+
+/// A tuple expression or pattern of the results in the range, e.g.
+/// `(res2, res3,)`.
+fn results(range: Range<usize>) -> String {
+    tuple("res", range)
+}
+
+/// A tuple expression or pattern of the spans in the range, e.g.
+/// `(span2, span3,)`.
+fn spans(range: Range<usize>) -> String {
+    tuple("span", range)
+}
+
+fn tuple(name: &str, range: Range<usize>) -> String {
+    format!(
+        "({})",
+        range
+            .map(|i| format!("{}{},", name, i))
+            .collect::<Vec<_>>()
+            .join(" ")
+    )
+}
+
+/// An expression for the end of the first `n` symbols of a rule, which is
+/// its start if there are none.
+fn end_of(n: usize) -> String {
+    if n == 0 {
+        "span.start".to_string()
+    } else {
+        format!("span{}.end", n)
+    }
+}
+
+/// An expression for the span of the first `n` symbols of a rule.
+fn span_to(n: usize) -> String {
+    format!("Span {{ start: span.start, end: {} }}", end_of(n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grammar(source: &str) -> Grammar {
+        source.parse().unwrap()
+    }
+
+    #[test]
+    fn eliminates_indirect_left_recursion() {
+        let mut grammar = grammar(
+            "terminal a = 'a'; terminal b = 'b'; terminal c = 'c';
+             S = A;
+             A = B a => { () };
+             B = A b => { () } | c;",
+        );
+        let origins = grammar.eliminate_left_recursion().unwrap();
+
+        assert!(Analysis::new(&grammar).left_recursion(&grammar).is_empty());
+        assert_eq!(origins.len(), grammar.rules.len());
+        // The rules of `S` are not on the cycle and stay first
+        assert_eq!(origins[0], 0);
+        // Only `B` is left-recursive once `A` is substituted into it
+        let names: Vec<_> = grammar.non_terminals.iter().map(|nt| &nt.name).collect();
+        assert_eq!(names, ["S", "A", "B", "B'"]);
+    }
+
+    #[test]
+    fn left_recursion_behind_nullable_symbol_is_kept() {
+        let mut grammar = grammar(
+            "terminal a = 'a';
+             S = N S a => { () } | a;
+             N = ε;",
+        );
+        let rules = format!("{:?}", grammar.rules);
+
+        assert!(matches!(
+            grammar.eliminate_left_recursion(),
+            Err(GrammarError::LeftRecursive { .. })
+        ));
+        assert_eq!(format!("{:?}", grammar.rules), rules);
+        assert_eq!(grammar.non_terminals.len(), 2);
+    }
+
+    #[test]
+    fn factors_longest_common_prefix() {
+        let mut grammar = grammar(
+            "terminal a = 'a'; terminal b = 'b'; terminal c = 'c';
+             S = a b c => { () } | a b => { () } | c;",
+        );
+        let origins = grammar.left_factor().unwrap();
+
+        assert!(Analysis::new(&grammar).all_conflicts(&grammar).is_empty());
+        // `S -> a b S' | c` and `S' -> c | ε`
+        assert_eq!(origins, [0, 2, 0, 1]);
+        let names: Vec<_> = grammar.non_terminals.iter().map(|nt| &nt.name).collect();
+        assert_eq!(names, ["S", "S'"]);
+    }
+}
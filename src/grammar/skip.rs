@@ -0,0 +1,299 @@
+//! Input the generated parser skips between terminals, like whitespace and
+//! comments, so that the rules do not need to mention it.
+
+use std::collections::{BTreeMap, HashSet};
+
+use super::{
+    error::GrammarError,
+    generator::{
+        CodeFile,
+        gen_source::{gen_byte_pattern, gen_expected},
+    },
+};
+
+/// A comment between two delimiters, like `/* */`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockComment {
+    pub start: Vec<u8>,
+    pub end: Vec<u8>,
+    /// Whether comments can contain other comments, so that every start
+    /// delimiter needs its own end delimiter.
+    pub nested: bool,
+}
+
+/// The bytes and comments skipped before every terminal.
+///
+/// The generated parser only peeks at one byte, so once it sees the first
+/// byte of a comment delimiter it commits to the comment. No terminal may
+/// therefore start with a skipped byte or the first byte of a delimiter, see
+/// [`Grammar::validate`](super::Grammar::validate). With a
+/// [lexer](super::Grammar::lexer), the tokenizer sees the whole input, so
+/// only the skipped bytes are off limits.
+#[derive(Debug, Clone, Default)]
+pub struct SkipSet {
+    bytes: HashSet<u8>,
+    line_comments: Vec<Vec<u8>>,
+    block_comments: Vec<BlockComment>,
+}
+
+/// A comment as seen by the generated code.
+#[derive(Clone, Copy)]
+enum Comment<'a> {
+    Line,
+    Block(&'a BlockComment),
+}
+
+impl SkipSet {
+    pub fn new() -> Self {
+        SkipSet::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty() && self.line_comments.is_empty() && self.block_comments.is_empty()
+    }
+
+    pub fn bytes(&self) -> &HashSet<u8> {
+        &self.bytes
+    }
+
+    pub fn line_comments(&self) -> &[Vec<u8>] {
+        &self.line_comments
+    }
+
+    pub fn block_comments(&self) -> &[BlockComment] {
+        &self.block_comments
+    }
+
+    /// The bytes skipped input can start with.
+    pub fn first_bytes(&self) -> HashSet<u8> {
+        let mut first = self.bytes.clone();
+        first.extend(self.delimiters().filter_map(|delimiter| delimiter.first()));
+        first
+    }
+
+    /// Skips each of the bytes, e.g. whitespace.
+    pub fn add_bytes(&mut self, bytes: impl IntoIterator<Item = u8>) -> Result<(), GrammarError> {
+        for byte in bytes {
+            if let Some(delimiter) = self.delimiters().find(|d| d.first() == Some(&byte)) {
+                return Err(GrammarError::InvalidSkip {
+                    message: format!(
+                        "'{}' starts the comment delimiter \"{}\"",
+                        byte.escape_ascii(),
+                        delimiter.escape_ascii()
+                    ),
+                });
+            }
+            self.bytes.insert(byte);
+        }
+
+        Ok(())
+    }
+
+    /// Skips comments from the delimiter to the end of the line. The newline
+    /// itself is only skipped if it is one of the skipped bytes.
+    pub fn add_line_comment(&mut self, start: &[u8]) -> Result<(), GrammarError> {
+        self.check_delimiter(start)?;
+        self.line_comments.push(start.to_vec());
+        Ok(())
+    }
+
+    pub fn add_block_comment(&mut self, comment: BlockComment) -> Result<(), GrammarError> {
+        self.check_delimiter(&comment.start)?;
+        if comment.end.is_empty() {
+            return Err(GrammarError::InvalidSkip {
+                message: "empty comment delimiter".to_string(),
+            });
+        }
+        self.block_comments.push(comment);
+        Ok(())
+    }
+
+    /// The delimiters that start comments.
+    fn delimiters(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.line_comments
+            .iter()
+            .chain(self.block_comments.iter().map(|comment| &comment.start))
+    }
+
+    /// A new start delimiter must not be empty, start with a skipped byte or
+    /// share a prefix with another delimiter. Otherwise the generated code
+    /// could not tell the comments apart with one byte of lookahead.
+    fn check_delimiter(&self, start: &[u8]) -> Result<(), GrammarError> {
+        let Some(first) = start.first() else {
+            return Err(GrammarError::InvalidSkip {
+                message: "empty comment delimiter".to_string(),
+            });
+        };
+        if self.bytes.contains(first) {
+            return Err(GrammarError::InvalidSkip {
+                message: format!(
+                    "comment delimiter \"{}\" starts with a skipped byte",
+                    start.escape_ascii()
+                ),
+            });
+        }
+        if let Some(other) = self
+            .delimiters()
+            .find(|other| other.starts_with(start) || start.starts_with(other))
+        {
+            return Err(GrammarError::InvalidSkip {
+                message: format!(
+                    "comment delimiters \"{}\" and \"{}\" are prefixes of each other",
+                    start.escape_ascii(),
+                    other.escape_ascii()
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Generates `_skip_one`, which consumes one skipped byte or comment and
+    /// tells whether there was one.
+    pub fn gen_function(&self, file: &mut CodeFile) {
+        let comments: Vec<(&[u8], Comment)> = self
+            .line_comments
+            .iter()
+            .map(|start| (start.as_slice(), Comment::Line))
+            .chain(
+                self.block_comments
+                    .iter()
+                    .map(|comment| (comment.start.as_slice(), Comment::Block(comment))),
+            )
+            .collect();
+
+        file.push_line("// Skips whitespace or a comment");
+        file.push_line(
+            "fn _skip_one(input: &mut Input<'_, impl Iterator<Item = u8>>) -> Result<bool, ParseError> {",
+        );
+        file.push_line("    match input.peek() {");
+        for (byte, comments) in group_by_byte(&comments, 0) {
+            file.push_line(format!("        Some({}) => {{", byte));
+            file.push_line("            input.next();");
+            gen_comment_match(&comments, 1, 12, file);
+            file.push_line("        }");
+        }
+        if !self.bytes.is_empty() {
+            file.push_line(format!(
+                "        Some({}) => {{",
+                gen_byte_pattern(&self.bytes)
+            ));
+            file.push_line("            input.next();");
+            file.push_line("        }");
+        }
+        file.push_line("        _ => return Ok(false),");
+        file.push_line("    }");
+        file.push_line("    Ok(true)");
+        file.push_line("}");
+        file.push_line("");
+    }
+}
+
+/// Groups the comments by the byte of their start delimiter at `depth`.
+fn group_by_byte<'a>(
+    comments: &[(&'a [u8], Comment<'a>)],
+    depth: usize,
+) -> BTreeMap<u8, Vec<(&'a [u8], Comment<'a>)>> {
+    let mut groups: BTreeMap<u8, Vec<_>> = BTreeMap::new();
+    for (start, comment) in comments {
+        groups
+            .entry(start[depth])
+            .or_default()
+            .push((*start, *comment));
+    }
+    groups
+}
+
+/// Generates the code after the first `depth` bytes of the start delimiters
+/// have been consumed. No delimiter is a prefix of another, so a single
+/// comment left is one whose delimiter is complete.
+fn gen_comment_match(
+    comments: &[(&[u8], Comment)],
+    depth: usize,
+    indent: usize,
+    file: &mut CodeFile,
+) {
+    let pad = " ".repeat(indent);
+
+    if let [(start, comment)] = comments
+        && start.len() == depth
+    {
+        match comment {
+            Comment::Line => {
+                file.push_line(format!("{}// Line comment: {}", pad, start.escape_ascii()));
+                file.push_line(format!(
+                    "{}while !matches!(input.peek(), Some(10) | None) {{",
+                    pad
+                ));
+                file.push_line(format!("{}    input.next();", pad));
+                file.push_line(format!("{}}}", pad));
+            }
+            Comment::Block(block) => gen_block_comment(block, &pad, file),
+        }
+        return;
+    }
+
+    file.push_line(format!("{}match input.peek() {{", pad));
+    for (byte, comments) in group_by_byte(comments, depth) {
+        file.push_line(format!("{}    Some({}) => {{", pad, byte));
+        file.push_line(format!("{}        input.next();", pad));
+        gen_comment_match(&comments, depth + 1, indent + 8, file);
+        file.push_line(format!("{}    }}", pad));
+    }
+    file.push_line(format!(
+        "{}    _ => return Err(input.error(\"comment\", {})),",
+        pad,
+        gen_expected(comments.iter().map(|(start, _)| delimiter(start)))
+    ));
+    file.push_line(format!("{}}}", pad));
+}
+
+/// Generates a loop consuming the comment up to its end delimiter. The
+/// bytes since the last delimiter are kept to recognize the delimiters.
+fn gen_block_comment(block: &BlockComment, pad: &str, file: &mut CodeFile) {
+    file.push_line(format!(
+        "{}// Block comment: {} {}",
+        pad,
+        block.start.escape_ascii(),
+        block.end.escape_ascii()
+    ));
+    file.push_line(format!("{}let mut window = Vec::new();", pad));
+    if block.nested {
+        file.push_line(format!("{}let mut depth = 1;", pad));
+    }
+    file.push_line(format!("{}loop {{", pad));
+    file.push_line(format!("{}    match input.next() {{", pad));
+    file.push_line(format!("{}        Some(byte) => window.push(byte),", pad));
+    file.push_line(format!(
+        "{}        None => return Err(input.error(\"comment\", {})),",
+        pad,
+        gen_expected([delimiter(&block.end)])
+    ));
+    file.push_line(format!("{}    }}", pad));
+    file.push_line(format!(
+        "{}    if window.ends_with(&{:?}) {{",
+        pad, block.end
+    ));
+    if block.nested {
+        file.push_line(format!("{}        depth -= 1;", pad));
+        file.push_line(format!("{}        if depth == 0 {{", pad));
+        file.push_line(format!("{}            break;", pad));
+        file.push_line(format!("{}        }}", pad));
+        file.push_line(format!("{}        window.clear();", pad));
+        file.push_line(format!(
+            "{}    }} else if window.ends_with(&{:?}) {{",
+            pad, block.start
+        ));
+        file.push_line(format!("{}        depth += 1;", pad));
+        file.push_line(format!("{}        window.clear();", pad));
+    } else {
+        file.push_line(format!("{}        break;", pad));
+    }
+    file.push_line(format!("{}    }}", pad));
+    file.push_line(format!("{}}}", pad));
+}
+
+/// A delimiter in the expected set of an error, like `"/*"`.
+fn delimiter(bytes: &[u8]) -> String {
+    format!("\"{}\"", bytes.escape_ascii())
+}
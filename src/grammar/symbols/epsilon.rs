@@ -1,6 +1,6 @@
 use crate::grammar::{Grammar, StrRepr};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Epsilon;
 
 impl Epsilon {
@@ -1,16 +1,141 @@
-use crate::grammar::{Grammar, StrRepr};
+use crate::grammar::{
+    Grammar, StrRepr,
+    error::GrammarError,
+    symbols::refs::{NonTerminalRef, SymbolRef, TerminalRef},
+};
 
 #[derive(Debug, Clone)]
 pub struct NonTerminal {
     pub name: String,
     /// The AST type of the non-terminal
     pub ast_type: String,
+    pub kind: NonTerminalKind,
+    /// How the generated parser goes on after an error in the non-terminal,
+    /// instead of stopping at it.
+    pub recovery: Option<Recovery>,
+    /// What errors in a non-terminal added by the grammar say is being
+    /// parsed, instead of its name that the user does not know, e.g.
+    /// `repetition in S`.
+    pub description: Option<String>,
 }
 
 impl NonTerminal {
     pub fn new(name: String, ast_type: String) -> Self {
-        NonTerminal { name, ast_type }
+        NonTerminal {
+            name,
+            ast_type,
+            kind: NonTerminalKind::Rules,
+            recovery: None,
+            description: None,
+        }
     }
+
+    /// The description of the non-terminal, or else its name.
+    pub fn describe(&self) -> &str {
+        self.description.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Checks the symbols its kind and recovery refer to, which are not part
+    /// of its rules.
+    pub fn test_indices(&self, grammar: &Grammar) -> Result<(), GrammarError> {
+        match &self.kind {
+            NonTerminalKind::Rules | NonTerminalKind::Inlined => {}
+            NonTerminalKind::Repeat { item } => item.test_index(grammar)?,
+            NonTerminalKind::List {
+                item, separator, ..
+            } => {
+                item.test_index(grammar)?;
+                separator.test_index(grammar)?;
+            }
+            NonTerminalKind::Operators { operand, operators } => {
+                operand.test_index(grammar)?;
+                for operator in operators {
+                    operator.symbol.test_index(grammar)?;
+                }
+            }
+        }
+        for t_ref in self.recovery.iter().flat_map(|r| r.sync.iter().flatten()) {
+            t_ref.test_index(grammar)?;
+        }
+        Ok(())
+    }
+}
+
+/// Panic-mode recovery from an error in a non-terminal: the generated parser
+/// records the error, skips input up to a lookahead of the sync set, and
+/// evaluates the error transform in place of the non-terminal's result.
+#[derive(Debug, Clone)]
+pub struct Recovery {
+    /// The terminals to skip to. Without them, the parser skips to the
+    /// FOLLOW set of the non-terminal. It always stops at the end of the
+    /// input.
+    pub sync: Option<Vec<TerminalRef>>,
+    /// Sees the error as `error` and the span from the start of the
+    /// non-terminal to the end of the skipped input as `span`.
+    pub error_transform: String,
+}
+
+/// How the generated parser parses a non-terminal. Every kind has rules, so
+/// that the analysis can treat all non-terminals alike.
+#[derive(Debug, Clone, Default)]
+pub enum NonTerminalKind {
+    /// Chooses one of its rules by the lookahead.
+    #[default]
+    Rules,
+    /// A repetition, parsed with a loop into a `Vec` of the item results. Its
+    /// rules describe the same repetition recursively.
+    Repeat { item: SymbolRef },
+    /// A separated list, parsed with a loop into a `Vec` of the item results.
+    /// Its rules describe the same list recursively.
+    List {
+        item: SymbolRef,
+        separator: SymbolRef,
+        trailing: Trailing,
+    },
+    /// Part of the rules of another non-terminal that parses it inline, so no
+    /// function is generated for it.
+    Inlined,
+    /// An expression parsed by precedence climbing. `operand` parses the
+    /// operands, the rules of this non-terminal describe the operators to the
+    /// analysis, ignoring their precedence.
+    Operators {
+        operand: NonTerminalRef,
+        operators: Vec<Operator>,
+    },
+}
+
+/// An operator of a [`NonTerminalKind::Operators`] non-terminal.
+#[derive(Debug, Clone)]
+pub struct Operator {
+    pub fixity: Fixity,
+    /// Operators with a higher precedence bind tighter.
+    pub precedence: u32,
+    pub symbol: SymbolRef,
+    /// Like the transform of a rule `E -> E op E` for infix operators,
+    /// `E -> op E` for prefix and `E -> E op` for postfix operators.
+    pub transform: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fixity {
+    Prefix,
+    Postfix,
+    Infix(Associativity),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+/// Whether a separated list may end with a separator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Trailing {
+    Allow,
+    #[default]
+    Forbid,
+    Require,
 }
 
 impl StrRepr for NonTerminal {
@@ -1,11 +1,7 @@
-use super::{
-    epsilon::Epsilon,
-    non_terminal::NonTerminal,
-    terminal::{ByteTerminal, TerminalLike},
-};
-use crate::grammar::{Grammar, StrRepr};
+use super::{epsilon::Epsilon, non_terminal::NonTerminal, terminal::TerminalLike};
+use crate::grammar::{Grammar, StrRepr, error::GrammarError};
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct TerminalRef(usize);
 
 impl TerminalRef {
@@ -13,20 +9,32 @@ impl TerminalRef {
         TerminalRef(index)
     }
 
-    pub fn deref<'a>(&self, grammar: &'a Grammar) -> &'a Box<dyn TerminalLike> {
+    /// Panics if the reference does not point into the grammar. References
+    /// returned by [`Grammar::add_terminal`] always do, and so do all
+    /// references in a grammar that [`Grammar::validate`] finds no errors in.
+    /// Code generation validates the grammar before it dereferences anything.
+    /// Use [`TerminalRef::try_deref`] for references that may not.
+    pub fn deref<'a>(&self, grammar: &'a Grammar) -> &'a dyn TerminalLike {
         grammar
             .get_terminal(self.0)
             .expect("index did not point to a terminal")
     }
 
+    pub fn try_deref<'a>(
+        &self,
+        grammar: &'a Grammar,
+    ) -> Result<&'a dyn TerminalLike, GrammarError> {
+        grammar
+            .get_terminal(self.0)
+            .ok_or(GrammarError::IndexOutOfBounds(SymbolRef::Terminal(*self)))
+    }
+
     pub fn index(&self) -> usize {
         self.0
     }
 
-    pub fn test_index(&self, grammar: &Grammar) {
-        if self.index() >= grammar.terminals.len() {
-            panic!("Terminal index out of bounds");
-        }
+    pub fn test_index(&self, grammar: &Grammar) -> Result<(), GrammarError> {
+        self.try_deref(grammar).map(|_| ())
     }
 }
 
@@ -44,12 +52,23 @@ impl NonTerminalRef {
         NonTerminalRef(index)
     }
 
+    /// Panics if the reference does not point into the grammar, see
+    /// [`TerminalRef::deref`]. References returned by
+    /// [`Grammar::add_non_terminal`] always do.
     pub fn deref<'a>(&self, grammar: &'a Grammar) -> &'a NonTerminal {
         grammar
             .get_non_terminal(self.0)
             .expect("index did not point to a non-terminal")
     }
 
+    pub fn try_deref<'a>(&self, grammar: &'a Grammar) -> Result<&'a NonTerminal, GrammarError> {
+        grammar
+            .get_non_terminal(self.0)
+            .ok_or(GrammarError::IndexOutOfBounds(SymbolRef::NonTerminal(
+                *self,
+            )))
+    }
+
     pub fn repr(&self, grammar: &Grammar) -> String {
         self.deref(grammar).repr(grammar)
     }
@@ -58,10 +77,8 @@ impl NonTerminalRef {
         self.0
     }
 
-    pub fn test_index(&self, grammar: &Grammar) {
-        if self.index() >= grammar.terminals.len() {
-            panic!("Non-terminal index out of bounds");
-        }
+    pub fn test_index(&self, grammar: &Grammar) -> Result<(), GrammarError> {
+        self.try_deref(grammar).map(|_| ())
     }
 }
 
@@ -106,7 +123,7 @@ impl SymbolRef {
         }
     }
 
-    pub fn unwrap_as_terminal<'a>(&self, grammar: &'a Grammar) -> &'a Box<dyn TerminalLike> {
+    pub fn unwrap_as_terminal<'a>(&self, grammar: &'a Grammar) -> &'a dyn TerminalLike {
         match &self {
             SymbolRef::Terminal(t_ref) => t_ref.deref(grammar),
             _ => panic!("Expected Terminal, found {:?}", self),
@@ -127,9 +144,9 @@ impl SymbolRef {
         }
     }
 
-    pub fn test_index(&self, grammar: &Grammar) {
+    pub fn test_index(&self, grammar: &Grammar) -> Result<(), GrammarError> {
         match self {
-            SymbolRef::Epsilon => {}
+            SymbolRef::Epsilon => Ok(()),
             SymbolRef::NonTerminal(nt_ref) => nt_ref.test_index(grammar),
             SymbolRef::Terminal(t_ref) => t_ref.test_index(grammar),
         }
@@ -1,13 +1,42 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, ops::RangeInclusive};
 
 use super::refs::TerminalRef;
-use crate::grammar::{Grammar, StrRepr, generator::CodeFile};
+use crate::grammar::{
+    Grammar, StrRepr,
+    analysis::Lookahead,
+    error::GrammarError,
+    generator::{
+        CodeFile,
+        gen_source::{gen_byte_pattern, gen_expected},
+    },
+    regex::{Regex, dfa::Dfa},
+};
 
 pub trait TerminalLike: StrRepr {
     fn name(&self) -> &str;
     fn first_bytes(&self) -> HashSet<u8>;
-    fn gen_inner_code(&self, t_ref: TerminalRef, file: &mut CodeFile) -> Result<(), String>;
+    /// One of the shortest inputs the terminal matches, used for examples in
+    /// diagnostics.
+    fn shortest_match(&self) -> Vec<u8>;
+    fn gen_inner_code(&self, t_ref: TerminalRef, file: &mut CodeFile) -> Result<(), GrammarError>;
+    /// Like [`TerminalLike::gen_inner_code`], for the lexing function of a
+    /// grammar with a [lexer](Grammar::lexer). Its input can be cloned to
+    /// look further ahead than one byte.
+    fn gen_lex_code(&self, t_ref: TerminalRef, file: &mut CodeFile) -> Result<(), GrammarError> {
+        self.gen_inner_code(t_ref, file)
+    }
     fn result_type(&self) -> String;
+    /// Whether the terminal only matches fixed words, like keywords. The
+    /// generated tokenizer prefers such terminals over others when both
+    /// match equally long tokens.
+    fn is_literal(&self) -> bool {
+        false
+    }
+    /// Whether the terminal decodes UTF-8 characters, so that its errors can
+    /// report a `Found::Char`.
+    fn decodes_chars(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -28,13 +57,16 @@ impl ByteTerminal {
         }
     }
 
-    pub fn from_char(char: char) -> Self {
-        ByteTerminal {
+    /// A terminal for an ASCII character. Other characters take more than
+    /// one byte in UTF-8, so they need a [`CharClassTerminal`] or a
+    /// [`TrieTerminal`] instead.
+    pub fn from_char(char: char) -> Option<Self> {
+        char.is_ascii().then(|| ByteTerminal {
             name: format!("char({})", char),
             byte: char as u8,
-            result_expr: Some(format!("'{}'", char)),
+            result_expr: Some(format!("{:?}", char)),
             result_type: "char".to_string(),
-        }
+        })
     }
 }
 
@@ -43,22 +75,35 @@ impl TerminalLike for ByteTerminal {
         &self.name
     }
 
+    fn is_literal(&self) -> bool {
+        true
+    }
+
     fn first_bytes(&self) -> HashSet<u8> {
         let mut set = HashSet::new();
         set.insert(self.byte);
         set
     }
 
-    fn gen_inner_code(&self, _t_ref: TerminalRef, file: &mut CodeFile) -> Result<(), String> {
-        file.push_line(format!("    let next = input.next();"));
-        file.push_line(format!("    if next == Some({}) {{", self.byte));
+    fn shortest_match(&self) -> Vec<u8> {
+        vec![self.byte]
+    }
+
+    fn gen_inner_code(&self, _t_ref: TerminalRef, file: &mut CodeFile) -> Result<(), GrammarError> {
+        file.push_line("    match input.peek().copied() {");
+        file.push_line(format!("        Some({}) => {{", self.byte));
+        file.push_line("            input.next();");
         file.push_line(format!(
-            "        return Ok({});",
+            "            return Ok({});",
             self.result_expr.clone().unwrap_or("()".to_string())
         ));
-        file.push_line(format!("    }} else {{"));
-        file.push_line(format!("        return Err(format!(\"Error parsing {}: Expected {:?} but found {{:?}}\", next));", self.name, self.byte));
-        file.push_line(format!("    }}"));
+        file.push_line("        }");
+        file.push_line(format!(
+            "        _ => return Err(input.error({:?}, {})),",
+            self.name,
+            gen_expected([Lookahead::Byte(self.byte).to_string()])
+        ));
+        file.push_line("    }");
 
         Ok(())
     }
@@ -74,6 +119,529 @@ impl StrRepr for ByteTerminal {
     }
 }
 
+/// A single byte out of a class of bytes, like `[a-zA-Z_]` or `[^"]`.
+#[derive(Debug, Clone)]
+pub struct ByteClassTerminal {
+    pub name: String,
+    pub ranges: Vec<RangeInclusive<u8>>,
+    /// Whether the terminal matches the bytes outside of the ranges instead.
+    pub negated: bool,
+    /// The result, which sees the matched byte as `byte`. Without one, the
+    /// result is the byte itself.
+    pub result_expr: Option<String>,
+    pub result_type: String,
+}
+
+impl ByteClassTerminal {
+    pub fn new(
+        name: String,
+        ranges: Vec<RangeInclusive<u8>>,
+        negated: bool,
+        result_expr: Option<String>,
+        result_type: String,
+    ) -> Self {
+        ByteClassTerminal {
+            name,
+            ranges,
+            negated,
+            result_expr,
+            result_type,
+        }
+    }
+
+    /// The class written like in a grammar file, for error messages.
+    pub fn pattern(&self) -> String {
+        let byte = |b: u8| match b {
+            b']' | b'-' | b'^' | b'\\' => format!("\\{}", b as char),
+            b' '..=b'~' => (b as char).to_string(),
+            _ => b.escape_ascii().to_string(),
+        };
+
+        let mut pattern = String::from(if self.negated { "[^" } else { "[" });
+        for range in &self.ranges {
+            pattern.push_str(&byte(*range.start()));
+            if range.start() != range.end() {
+                pattern.push('-');
+                pattern.push_str(&byte(*range.end()));
+            }
+        }
+        pattern.push(']');
+        pattern
+    }
+}
+
+impl TerminalLike for ByteClassTerminal {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn first_bytes(&self) -> HashSet<u8> {
+        (0..=u8::MAX)
+            .filter(|b| self.ranges.iter().any(|range| range.contains(b)) != self.negated)
+            .collect()
+    }
+
+    fn shortest_match(&self) -> Vec<u8> {
+        self.first_bytes().into_iter().min().into_iter().collect()
+    }
+
+    fn gen_inner_code(&self, _t_ref: TerminalRef, file: &mut CodeFile) -> Result<(), GrammarError> {
+        let bytes = self.first_bytes();
+
+        file.push_line("    match input.peek().copied() {");
+        if !bytes.is_empty() {
+            file.push_line(format!(
+                "        Some(byte @ ({})) => {{",
+                gen_byte_pattern(&bytes)
+            ));
+            file.push_line("            input.next();");
+            file.push_line(format!(
+                "            return Ok({});",
+                self.result_expr.clone().unwrap_or("byte".to_string())
+            ));
+            file.push_line("        }");
+        }
+        file.push_line(format!(
+            "        _ => return Err(input.error({:?}, {})),",
+            self.name,
+            gen_expected([self.pattern()])
+        ));
+        file.push_line("    }");
+
+        Ok(())
+    }
+
+    fn result_type(&self) -> String {
+        self.result_type.clone()
+    }
+}
+
+impl StrRepr for ByteClassTerminal {
+    fn repr(&self, _grammar: &Grammar) -> String {
+        format!("Class({})", self.name)
+    }
+}
+
+/// A Unicode property a [`CharClassTerminal`] can match. The generated
+/// parser checks them with the methods of `char`, which follow the Unicode
+/// properties of the same names rather than the general categories exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnicodeCategory {
+    /// The `Alphabetic` property: the letters `L`, `Nl` and some marks.
+    Letter,
+    /// The `Lowercase` property, mostly `Ll`.
+    Lowercase,
+    /// The `Uppercase` property, mostly `Lu`.
+    Uppercase,
+    /// The numbers `N`.
+    Number,
+    /// [`UnicodeCategory::Letter`] or [`UnicodeCategory::Number`].
+    Alphanumeric,
+    /// The `White_Space` property.
+    Whitespace,
+    /// The control characters `Cc`.
+    Control,
+}
+
+impl UnicodeCategory {
+    /// Looks up a category by its name or the abbreviation of the general
+    /// category it stands for, e.g. `Letter` or `L`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "Letter" | "L" => UnicodeCategory::Letter,
+            "Lowercase" | "Ll" => UnicodeCategory::Lowercase,
+            "Uppercase" | "Lu" => UnicodeCategory::Uppercase,
+            "Number" | "N" => UnicodeCategory::Number,
+            "Alphanumeric" => UnicodeCategory::Alphanumeric,
+            "Whitespace" => UnicodeCategory::Whitespace,
+            "Control" | "Cc" => UnicodeCategory::Control,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UnicodeCategory::Letter => "Letter",
+            UnicodeCategory::Lowercase => "Lowercase",
+            UnicodeCategory::Uppercase => "Uppercase",
+            UnicodeCategory::Number => "Number",
+            UnicodeCategory::Alphanumeric => "Alphanumeric",
+            UnicodeCategory::Whitespace => "Whitespace",
+            UnicodeCategory::Control => "Control",
+        }
+    }
+
+    /// The method of `char` that tests for the category.
+    fn method(&self) -> &'static str {
+        match self {
+            UnicodeCategory::Letter => "is_alphabetic",
+            UnicodeCategory::Lowercase => "is_lowercase",
+            UnicodeCategory::Uppercase => "is_uppercase",
+            UnicodeCategory::Number => "is_numeric",
+            UnicodeCategory::Alphanumeric => "is_alphanumeric",
+            UnicodeCategory::Whitespace => "is_whitespace",
+            UnicodeCategory::Control => "is_control",
+        }
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            UnicodeCategory::Letter => c.is_alphabetic(),
+            UnicodeCategory::Lowercase => c.is_lowercase(),
+            UnicodeCategory::Uppercase => c.is_uppercase(),
+            UnicodeCategory::Number => c.is_numeric(),
+            UnicodeCategory::Alphanumeric => c.is_alphanumeric(),
+            UnicodeCategory::Whitespace => c.is_whitespace(),
+            UnicodeCategory::Control => c.is_control(),
+        }
+    }
+}
+
+/// A single Unicode scalar value out of a class, like `u[\p{Letter}_]` or
+/// `u[^"]`. The generated code decodes the UTF-8 encoding of the character
+/// and fails on invalid UTF-8.
+///
+/// The analysis only sees the first byte of the encoding, so two classes
+/// with non-ASCII characters sharing a first byte conflict, even if the
+/// classes themselves are disjoint.
+#[derive(Debug, Clone)]
+pub struct CharClassTerminal {
+    pub name: String,
+    pub ranges: Vec<RangeInclusive<char>>,
+    pub categories: Vec<UnicodeCategory>,
+    /// Whether the terminal matches the characters outside of the class
+    /// instead.
+    pub negated: bool,
+    /// The result, which sees the matched `char` as `c`. Without one, the
+    /// result is the `char` itself.
+    pub result_expr: Option<String>,
+    pub result_type: String,
+    /// The first bytes of the encodings of the matched characters. Finding
+    /// them means testing every character, so it is only done once.
+    lead_bytes: HashSet<u8>,
+}
+
+impl CharClassTerminal {
+    pub fn new(
+        name: String,
+        ranges: Vec<RangeInclusive<char>>,
+        categories: Vec<UnicodeCategory>,
+        negated: bool,
+        result_expr: Option<String>,
+        result_type: String,
+    ) -> Self {
+        let mut terminal = CharClassTerminal {
+            name,
+            ranges,
+            categories,
+            negated,
+            result_expr,
+            result_type,
+            lead_bytes: HashSet::new(),
+        };
+        terminal.lead_bytes = (0..=char::MAX as u32)
+            .filter_map(char::from_u32)
+            .filter(|c| terminal.contains(*c))
+            .map(|c| c.encode_utf8(&mut [0; 4]).as_bytes()[0])
+            .collect();
+        terminal
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        let in_class = self.ranges.iter().any(|range| range.contains(&c))
+            || self.categories.iter().any(|category| category.contains(c));
+        in_class != self.negated
+    }
+
+    /// The class written like in a grammar file, for error messages.
+    pub fn pattern(&self) -> String {
+        let char = |c: char| match c {
+            ']' | '-' | '^' | '\\' => format!("\\{}", c),
+            _ if c.is_control() => c.escape_default().to_string(),
+            _ => c.to_string(),
+        };
+
+        let mut pattern = String::from(if self.negated { "u[^" } else { "u[" });
+        for category in &self.categories {
+            pattern.push_str(&format!("\\p{{{}}}", category.name()));
+        }
+        for range in &self.ranges {
+            pattern.push_str(&char(*range.start()));
+            if range.start() != range.end() {
+                pattern.push('-');
+                pattern.push_str(&char(*range.end()));
+            }
+        }
+        pattern.push(']');
+        pattern
+    }
+
+    /// A condition on the decoded `c` that holds for the class.
+    fn gen_condition(&self) -> String {
+        let mut conditions = Vec::new();
+        if !self.ranges.is_empty() {
+            conditions.push(format!(
+                "matches!(c, {})",
+                self.ranges
+                    .iter()
+                    .map(|range| if range.start() == range.end() {
+                        format!("{:?}", range.start())
+                    } else {
+                        format!("{:?}..={:?}", range.start(), range.end())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            ));
+        }
+        for category in &self.categories {
+            conditions.push(format!("c.{}()", category.method()));
+        }
+
+        match (conditions.is_empty(), self.negated) {
+            (true, false) => "false".to_string(),
+            (true, true) => "true".to_string(),
+            (false, false) => conditions.join(" || "),
+            (false, true) => format!("!({})", conditions.join(" || ")),
+        }
+    }
+}
+
+impl TerminalLike for CharClassTerminal {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn decodes_chars(&self) -> bool {
+        true
+    }
+
+    fn first_bytes(&self) -> HashSet<u8> {
+        self.lead_bytes.clone()
+    }
+
+    fn shortest_match(&self) -> Vec<u8> {
+        (0..=char::MAX as u32)
+            .filter_map(char::from_u32)
+            .find(|c| self.contains(*c))
+            .map(|c| c.to_string().into_bytes())
+            .unwrap_or_default()
+    }
+
+    fn gen_inner_code(&self, _t_ref: TerminalRef, file: &mut CodeFile) -> Result<(), GrammarError> {
+        let expected = gen_expected([self.pattern()]);
+        // Errors are at the start of the character, which is read first
+        let gen_error = |found: &str| {
+            format!(
+                "ParseError {{ pos, found: {}, parsing: {:?}, expected: {} }}",
+                found, self.name, expected
+            )
+        };
+
+        file.push_line("    let pos = input.pos();");
+        file.push_line("    let Some(first) = input.next() else {");
+        file.push_line(format!("        return Err({});", gen_error("Found::Eof")));
+        file.push_line("    };");
+        file.push_line("    // The length of the encoding, by its first byte");
+        file.push_line("    let len = match first {");
+        file.push_line("        0xc0..=0xdf => 2,");
+        file.push_line("        0xe0..=0xef => 3,");
+        file.push_line("        0xf0..=0xf7 => 4,");
+        file.push_line("        _ => 1,");
+        file.push_line("    };");
+        file.push_line("    let mut bytes = vec![first];");
+        file.push_line(
+            "    while bytes.len() < len && input.peek().is_some_and(|b| b & 0xc0 == 0x80) {",
+        );
+        file.push_line("        bytes.extend(input.next());");
+        file.push_line("    }");
+        file.push_line(
+            "    let Some(c) = std::str::from_utf8(&bytes).ok().and_then(|s| s.chars().next()) else {",
+        );
+        file.push_line(format!(
+            "        return Err({});",
+            gen_error("Found::Byte(first)")
+        ));
+        file.push_line("    };");
+        file.push_line(format!("    if {} {{", self.gen_condition()));
+        file.push_line(format!(
+            "        return Ok({});",
+            self.result_expr.clone().unwrap_or("c".to_string())
+        ));
+        file.push_line("    }");
+        file.push_line(format!("    return Err({});", gen_error("Found::Char(c)")));
+
+        Ok(())
+    }
+
+    fn result_type(&self) -> String {
+        self.result_type.clone()
+    }
+}
+
+impl StrRepr for CharClassTerminal {
+    fn repr(&self, _grammar: &Grammar) -> String {
+        format!("CharClass({})", self.name)
+    }
+}
+
+/// A terminal matching a regular expression, see [`crate::grammar::regex`]
+/// for the syntax. The generated code runs the minimized DFA of the regex.
+///
+/// The match is as long as possible. With a [lexer](Grammar::lexer), it is
+/// the longest prefix of the input the regex matches, so `ab(cd)?` matches
+/// `ab` of `abc`. Without one, the parser cannot rewind the input, so the
+/// match goes on while the next byte has a transition and does not fall back
+/// to a shorter one: `ab(cd)?` fails on `abc`.
+#[derive(Debug, Clone)]
+pub struct RegexTerminal {
+    pub name: String,
+    pub pattern: String,
+    pub dfa: Dfa,
+    /// The result, which sees the matched bytes as the `Vec<u8>` `bytes`.
+    /// Without one, the result is `bytes` itself.
+    pub result_expr: Option<String>,
+    pub result_type: String,
+}
+
+impl RegexTerminal {
+    pub fn new(
+        name: String,
+        pattern: String,
+        result_expr: Option<String>,
+        result_type: String,
+    ) -> Result<Self, GrammarError> {
+        let invalid = |message: String| GrammarError::InvalidRegex {
+            terminal: name.clone(),
+            message,
+        };
+
+        let dfa = Dfa::new(&Regex::parse(&pattern).map_err(invalid)?);
+        if !dfa.matches_anything() {
+            return Err(invalid("the regex matches nothing".to_string()));
+        }
+        // The analysis assumes that every terminal consumes input
+        if dfa.accepting[0] {
+            return Err(invalid("the regex matches the empty string".to_string()));
+        }
+
+        Ok(RegexTerminal {
+            name,
+            pattern,
+            dfa,
+            result_expr,
+            result_type,
+        })
+    }
+}
+
+impl TerminalLike for RegexTerminal {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn first_bytes(&self) -> HashSet<u8> {
+        self.dfa.first_bytes()
+    }
+
+    fn shortest_match(&self) -> Vec<u8> {
+        self.dfa.shortest_match()
+    }
+
+    fn gen_inner_code(&self, _t_ref: TerminalRef, file: &mut CodeFile) -> Result<(), GrammarError> {
+        file.push_line("    let mut bytes = Vec::new();");
+        file.push_line("    let mut state = 0usize;");
+        file.push_line("    loop {");
+        self.gen_transitions("input.peek()", file);
+        file.push_line("        bytes.extend(input.next());");
+        file.push_line("    }");
+
+        file.push_line(format!("    if {} {{", self.gen_accepting()));
+        file.push_line(format!("        return Ok({});", self.gen_result()));
+        file.push_line("    }");
+        file.push_line(format!("    return Err({});", self.gen_error()));
+
+        Ok(())
+    }
+
+    /// Runs the DFA on a clone of the input and remembers the last accepting
+    /// state it passes, and then only consumes the input up to there.
+    fn gen_lex_code(&self, _t_ref: TerminalRef, file: &mut CodeFile) -> Result<(), GrammarError> {
+        file.push_line("    let mut ahead = input.inner.clone();");
+        file.push_line("    let mut state = 0usize;");
+        file.push_line("    let mut len = 0;");
+        file.push_line("    let mut accepted = None;");
+        file.push_line("    loop {");
+        self.gen_transitions("ahead.next().as_ref()", file);
+        file.push_line("        len += 1;");
+        file.push_line(format!("        if {} {{", self.gen_accepting()));
+        file.push_line("            accepted = Some(len);");
+        file.push_line("        }");
+        file.push_line("    }");
+
+        file.push_line("    let Some(len) = accepted else {");
+        file.push_line(format!("        return Err({});", self.gen_error()));
+        file.push_line("    };");
+        file.push_line("    let bytes: Vec<u8> = (0..len).filter_map(|_| input.next()).collect();");
+        file.push_line(format!("    return Ok({});", self.gen_result()));
+
+        Ok(())
+    }
+
+    fn result_type(&self) -> String {
+        self.result_type.clone()
+    }
+}
+
+impl RegexTerminal {
+    /// A statement moving `state` along the transition for the byte `next`,
+    /// an `Option<&u8>`, or breaking out of the loop around it if there is
+    /// none.
+    fn gen_transitions(&self, next: &str, file: &mut CodeFile) {
+        file.push_line(format!("        state = match (state, {}) {{", next));
+        for state in 0..self.dfa.state_count() {
+            for (bytes, target) in self.dfa.grouped_transitions(state) {
+                file.push_line(format!(
+                    "            ({}, Some({})) => {},",
+                    state,
+                    gen_byte_pattern(&bytes),
+                    target
+                ));
+            }
+        }
+        file.push_line("            _ => break,");
+        file.push_line("        };");
+    }
+
+    /// A condition that holds if `state` is accepting.
+    fn gen_accepting(&self) -> String {
+        let accepting = (0..self.dfa.state_count())
+            .filter(|state| self.dfa.accepting[*state])
+            .map(|state| state.to_string())
+            .collect::<Vec<_>>();
+        format!("matches!(state, {})", accepting.join(" | "))
+    }
+
+    fn gen_result(&self) -> String {
+        self.result_expr.clone().unwrap_or("bytes".to_string())
+    }
+
+    fn gen_error(&self) -> String {
+        format!(
+            "input.error({:?}, {})",
+            self.name,
+            gen_expected([format!("/{}/", self.pattern)])
+        )
+    }
+}
+
+impl StrRepr for RegexTerminal {
+    fn repr(&self, _grammar: &Grammar) -> String {
+        format!("Regex({})", self.name)
+    }
+}
+
+/// A node of a [`TrieTerminal`]. A node with a result ends a word, which
+/// may continue into longer words through its children.
 #[derive(Debug, Clone)]
 pub struct TrieNode {
     pub children: Vec<TrieNode>,
@@ -90,87 +658,200 @@ impl TrieNode {
         }
     }
 
-    fn add_word(&mut self, word: &[u8], result: String) -> Result<(), String> {
-        if word.is_empty() {
-            return Err(format!(
-                "Word already exists in trie, or is prefix of existing word: {:?}",
-                word
-            ));
-        }
-
-        if let Some(node) = self.children.iter_mut().find(|n| n.byte == word[0]) {
-            node.add_word(&word[1..], result)
-        } else {
-            let new_node = if word.len() == 1 {
-                TrieNode::new(word[0], Some(result))
-            } else {
-                let mut new_node = TrieNode::new(word[0], None);
-                new_node.add_word(&word[1..], result)?;
-                new_node
-            };
+    /// Adds the rest of a word below this node. Returns `false` if the word
+    /// is already in the trie.
+    fn add_word(&mut self, word: &[u8], result: String) -> bool {
+        let Some((&byte, rest)) = word.split_first() else {
+            if self.result.is_some() {
+                return false;
+            }
+            self.result = Some(result);
+            return true;
+        };
 
-            self.children.push(new_node);
-            Ok(())
-        }
+        let index = match self.children.iter().position(|n| n.byte == byte) {
+            Some(index) => index,
+            None => {
+                self.children.push(TrieNode::new(byte, None));
+                self.children.len() - 1
+            }
+        };
+        self.children[index].add_word(rest, result)
     }
+}
+
+/// Generates a match on the next byte that consumes it and continues with
+/// the node of that byte. If no node fits, the word ends with the result of
+/// the node matched so far, or else the input is rewound to the end of the
+/// last word passed, `fallback`, which ends there instead.
+fn gen_trie_match(
+    children: &[TrieNode],
+    result: Option<&String>,
+    fallback: Option<&String>,
+    trie: &TrieTerminal,
+    indent: usize,
+    file: &mut CodeFile,
+) {
+    let pad = " ".repeat(indent);
 
-    fn gen_match(&self, trie: &TrieTerminal, file: &mut CodeFile) -> Result<(), String> {
-        file.push_line(format!("let next = input.next();"));
-        file.push_line(format!("match next {{"));
+    // The bytes after a word are kept to put them back if a child that ends
+    // no word is not followed by a longer one
+    let fallback = match result {
+        Some(result) if children.iter().any(|child| child.result.is_none()) => {
+            file.push_line(format!("{}let word_end = input.pos();", pad));
+            file.push_line(format!("{}let mut read = Vec::new();", pad));
+            Some(result)
+        }
+        Some(_) => None,
+        None => fallback,
+    };
 
-        for child in &self.children {
-            file.push_line(format!("    Some({}) => {{", child.byte));
-            if let Some(ref result) = child.result {
-                file.push_line(format!("        return Ok({});", result));
-            } else {
-                child.gen_match(trie, file)?;
+    file.push_line(format!("{}match input.peek() {{", pad));
+    for child in children {
+        file.push_line(format!(
+            "{}    Some({}) => {{",
+            pad,
+            gen_byte_pattern(&trie.folded(child.byte))
+        ));
+        if fallback.is_some() {
+            file.push_line(format!("{}        read.extend(input.next());", pad));
+        } else {
+            file.push_line(format!("{}        input.next();", pad));
+        }
+        match &child.result {
+            Some(result) if child.children.is_empty() => {
+                gen_word_end(result, trie, indent + 8, file)
             }
-            file.push_line(format!("    }}"));
+            result => gen_trie_match(
+                &child.children,
+                result.as_ref(),
+                fallback,
+                trie,
+                indent + 8,
+                file,
+            ),
         }
-        file.push_line(format!("    _ => {{"));
-        file.push_line(format!("        return Err(format!(\"Error parsing {}: Expected {:?} but found {{:?}}\", next));", trie.name, trie.words));
-        file.push_line(format!("    }}"));
-        file.push_line(format!("}}"));
+        file.push_line(format!("{}    }}", pad));
+    }
+    file.push_line(format!("{}    _ => {{", pad));
+    match (result, fallback) {
+        (Some(result), _) => gen_word_end(result, trie, indent + 8, file),
+        (None, Some(fallback)) => {
+            file.push_line(format!("{}        input.rewind(word_end, read);", pad));
+            gen_word_end(fallback, trie, indent + 8, file);
+        }
+        (None, None) => file.push_line(format!("{}        {}", pad, trie.gen_error())),
+    }
+    file.push_line(format!("{}    }}", pad));
+    file.push_line(format!("{}}}", pad));
+}
 
-        Ok(())
+/// Generates the return of a matched word, after checking that the next
+/// byte is not one that must not follow it.
+fn gen_word_end(result: &str, trie: &TrieTerminal, indent: usize, file: &mut CodeFile) {
+    let pad = " ".repeat(indent);
+
+    if !trie.options.not_followed_by.is_empty() {
+        file.push_line(format!(
+            "{}if matches!(input.peek(), Some({})) {{",
+            pad,
+            gen_byte_pattern(&trie.options.not_followed_by)
+        ));
+        file.push_line(format!("{}    {}", pad, trie.gen_error()));
+        file.push_line(format!("{}}}", pad));
     }
+    file.push_line(format!("{}return Ok({});", pad, result));
+}
+
+/// Options of a [`TrieTerminal`], which must be set before words are added.
+#[derive(Debug, Clone, Default)]
+pub struct TrieOptions {
+    /// Matches ASCII letters regardless of their case.
+    pub case_insensitive: bool,
+    /// Bytes that must not follow a word, so that e.g. a keyword does not
+    /// match the start of a longer identifier.
+    pub not_followed_by: HashSet<u8>,
 }
 
+/// A terminal matching one of a set of words. It matches the longest word
+/// it can: after a word, it goes on while the next byte continues a longer
+/// word, and falls back to the word if none does, so with the words `a` and
+/// `abc` it matches `a` of `abd`.
 #[derive(Debug, Clone)]
 pub struct TrieTerminal {
     pub name: String,
     pub children: Vec<TrieNode>,
     pub words: Vec<Box<[u8]>>,
     pub result_type: String,
+    pub options: TrieOptions,
 }
 
 impl TrieTerminal {
     pub fn new(name: String, result_type: String) -> Self {
+        TrieTerminal::with_options(name, result_type, TrieOptions::default())
+    }
+
+    pub fn with_options(name: String, result_type: String, options: TrieOptions) -> Self {
         TrieTerminal {
             name,
             children: Vec::new(),
             words: Vec::new(),
             result_type,
+            options,
         }
     }
 
-    pub fn add_word(&mut self, word: &[u8], result: String) -> Result<(), String> {
-        if word.is_empty() {
-            return Err(format!("Empty word cannot be added to trie"));
-        }
-        for child in &mut self.children {
-            if child.byte == word[0] {
-                child.add_word(&word[1..], result)?;
-                self.words.push(word.to_vec().into_boxed_slice());
-                return Ok(());
+    pub fn add_word(&mut self, word: &[u8], result: String) -> Result<(), GrammarError> {
+        // Case-insensitive words are stored in lowercase
+        let key = if self.options.case_insensitive {
+            word.to_ascii_lowercase()
+        } else {
+            word.to_vec()
+        };
+        let Some((&byte, rest)) = key.split_first() else {
+            return Err(GrammarError::EmptyWord {
+                terminal: self.name.clone(),
+            });
+        };
+
+        let index = match self.children.iter().position(|n| n.byte == byte) {
+            Some(index) => index,
+            None => {
+                self.children.push(TrieNode::new(byte, None));
+                self.children.len() - 1
             }
+        };
+        if !self.children[index].add_word(rest, result) {
+            return Err(GrammarError::WordConflict {
+                terminal: self.name.clone(),
+                word: word.to_vec(),
+            });
         }
-        let mut new_node = TrieNode::new(word[0], None);
-        new_node.add_word(&word[1..], result)?;
-        self.children.push(new_node);
+
         self.words.push(word.to_vec().into_boxed_slice());
         Ok(())
     }
+
+    /// The bytes a byte of the trie matches in the input.
+    fn folded(&self, byte: u8) -> HashSet<u8> {
+        if self.options.case_insensitive {
+            HashSet::from([byte, byte.to_ascii_uppercase()])
+        } else {
+            HashSet::from([byte])
+        }
+    }
+
+    fn gen_error(&self) -> String {
+        format!(
+            "return Err(input.error({:?}, {}));",
+            self.name,
+            gen_expected(
+                self.words
+                    .iter()
+                    .map(|word| format!("\"{}\"", word.escape_ascii()))
+            )
+        )
+    }
 }
 
 impl TerminalLike for TrieTerminal {
@@ -178,30 +859,27 @@ impl TerminalLike for TrieTerminal {
         &self.name
     }
 
+    fn is_literal(&self) -> bool {
+        true
+    }
+
     fn first_bytes(&self) -> HashSet<u8> {
-        let mut set = HashSet::new();
-        for child in &self.children {
-            set.insert(child.byte);
-        }
-        set
+        self.children
+            .iter()
+            .flat_map(|child| self.folded(child.byte))
+            .collect()
     }
 
-    fn gen_inner_code(&self, _t_ref: TerminalRef, file: &mut CodeFile) -> Result<(), String> {
-        file.push_line(format!("    let next = input.next();"));
-        file.push_line(format!("    match next {{"));
-        for child in &self.children {
-            file.push_line(format!("        Some({}) => {{", child.byte));
-            if let Some(ref result) = child.result {
-                file.push_line(format!("            return Ok({});", result));
-            } else {
-                child.gen_match(&self, file)?;
-            }
-            file.push_line(format!("        }}"));
-        }
-        file.push_line(format!("        _ => {{"));
-        file.push_line(format!("            return Err(format!(\"Error parsing {}: Expected {:?} but found {{:?}}\", next));", self.name, self.words));
-        file.push_line(format!("        }}"));
-        file.push_line(format!("    }}"));
+    fn shortest_match(&self) -> Vec<u8> {
+        self.words
+            .iter()
+            .min_by_key(|word| word.len())
+            .map(|word| word.to_vec())
+            .unwrap_or_default()
+    }
+
+    fn gen_inner_code(&self, _t_ref: TerminalRef, file: &mut CodeFile) -> Result<(), GrammarError> {
+        gen_trie_match(&self.children, None, None, self, 4, file);
 
         Ok(())
     }
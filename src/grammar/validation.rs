@@ -0,0 +1,400 @@
+use std::{collections::HashSet, fmt::Display};
+
+use super::{
+    Grammar,
+    symbols::refs::{NonTerminalRef, SymbolRef, TerminalRef},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// An issue found by [`Grammar::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// The start symbol is not set.
+    MissingStart,
+    /// A rule refers to a symbol that is not part of the grammar.
+    InvalidRule { rule: usize },
+    /// The list, operators or recovery of a non-terminal refer to a symbol
+    /// that is not part of the grammar.
+    InvalidNonTerminal { non_terminal: String },
+    /// A non-terminal has no rules.
+    NoRules { non_terminal: String },
+    /// A non-terminal cannot derive any string of terminals, so parsing it
+    /// can never succeed.
+    Unproductive { non_terminal: String },
+    /// A non-terminal cannot be reached from the start symbol.
+    Unreachable { non_terminal: String },
+    /// A terminal is not used by any reachable rule.
+    UnusedTerminal { terminal: String },
+    /// A terminal can start with a byte that is skipped before it, so it
+    /// could never match there. See [`SkipSet`](super::skip::SkipSet).
+    SkippedStart { terminal: String, byte: u8 },
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Diagnostic::MissingStart
+            | Diagnostic::InvalidRule { .. }
+            | Diagnostic::InvalidNonTerminal { .. }
+            | Diagnostic::NoRules { .. }
+            | Diagnostic::Unproductive { .. }
+            | Diagnostic::SkippedStart { .. } => Severity::Error,
+            Diagnostic::Unreachable { .. } | Diagnostic::UnusedTerminal { .. } => Severity::Warning,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity() == Severity::Error
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Diagnostic::MissingStart => write!(f, "Start symbol not set"),
+            Diagnostic::InvalidRule { rule } => {
+                write!(f, "Rule {} refers to a symbol that does not exist", rule)
+            }
+            Diagnostic::InvalidNonTerminal { non_terminal } => write!(
+                f,
+                "Non-terminal {} refers to a symbol that does not exist",
+                non_terminal
+            ),
+            Diagnostic::NoRules { non_terminal } => {
+                write!(f, "No rules for non-terminal {}", non_terminal)
+            }
+            Diagnostic::Unproductive { non_terminal } => write!(
+                f,
+                "Non-terminal {} does not derive any finite input",
+                non_terminal
+            ),
+            Diagnostic::Unreachable { non_terminal } => write!(
+                f,
+                "Non-terminal {} is not reachable from the start symbol",
+                non_terminal
+            ),
+            Diagnostic::UnusedTerminal { terminal } => {
+                write!(f, "Terminal {} is not used by any reachable rule", terminal)
+            }
+            Diagnostic::SkippedStart { terminal, byte } => write!(
+                f,
+                "Terminal {} can start with '{}', which is skipped",
+                terminal,
+                byte.escape_ascii()
+            ),
+        }
+    }
+}
+
+impl Grammar {
+    /// Checks the whole grammar and reports all issues at once, errors first.
+    /// A parser can only be generated if there are no errors.
+    ///
+    /// Without errors, every symbol reference in the grammar points into it,
+    /// so [dereferencing](TerminalRef::deref) any of them cannot panic.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for (i, rule) in self.rules.iter().enumerate() {
+            if rule.test_indices(self).is_err() {
+                diagnostics.push(Diagnostic::InvalidRule { rule: i });
+            }
+        }
+        for (nt, _) in self.iter_non_terminals() {
+            if nt.test_indices(self).is_err() {
+                diagnostics.push(Diagnostic::InvalidNonTerminal {
+                    non_terminal: nt.name.clone(),
+                });
+            }
+        }
+        // The remaining checks need to follow the symbols of every rule
+        if !diagnostics.is_empty() {
+            return diagnostics;
+        }
+
+        if self
+            .start
+            .is_none_or(|start| start.test_index(self).is_err())
+        {
+            diagnostics.push(Diagnostic::MissingStart);
+        }
+
+        let productive = self.productive_non_terminals();
+        for (nt, nt_ref) in self.iter_non_terminals() {
+            if self.get_rules_for_non_terminal(nt).is_empty() {
+                diagnostics.push(Diagnostic::NoRules {
+                    non_terminal: nt.name.clone(),
+                });
+            } else if !productive.contains(&nt_ref) {
+                diagnostics.push(Diagnostic::Unproductive {
+                    non_terminal: nt.name.clone(),
+                });
+            }
+        }
+
+        // A lexer can look past the first byte of a comment delimiter
+        let skipped = if self.lexer {
+            self.skip.bytes().clone()
+        } else {
+            self.skip.first_bytes()
+        };
+        for (t, _) in self.iter_terminals() {
+            if let Some(byte) = t.first_bytes().intersection(&skipped).min() {
+                diagnostics.push(Diagnostic::SkippedStart {
+                    terminal: t.name().to_string(),
+                    byte: *byte,
+                });
+            }
+        }
+
+        if let Some(start) = self.start.filter(|start| start.test_index(self).is_ok()) {
+            let (reachable, used_terminals) = self.reachable_symbols(start);
+
+            for (nt, nt_ref) in self.iter_non_terminals() {
+                if !reachable.contains(&nt_ref) {
+                    diagnostics.push(Diagnostic::Unreachable {
+                        non_terminal: nt.name.clone(),
+                    });
+                }
+            }
+
+            for (t, t_ref) in self.iter_terminals() {
+                if !used_terminals.contains(&t_ref) {
+                    diagnostics.push(Diagnostic::UnusedTerminal {
+                        terminal: t.name().to_string(),
+                    });
+                }
+            }
+        }
+
+        diagnostics.sort_by_key(|d| d.severity());
+        diagnostics
+    }
+
+    /// The non-terminals that derive at least one string of terminals.
+    fn productive_non_terminals(&self) -> HashSet<NonTerminalRef> {
+        let mut productive = HashSet::new();
+
+        loop {
+            let mut changed = false;
+
+            for rule in &self.rules {
+                if productive.contains(&rule.lhs) {
+                    continue;
+                }
+
+                let is_productive = rule.rhs.iter().all(|symbol| match symbol {
+                    SymbolRef::NonTerminal(nt_ref) => productive.contains(nt_ref),
+                    SymbolRef::Terminal(_) | SymbolRef::Epsilon => true,
+                });
+
+                if is_productive {
+                    productive.insert(rule.lhs);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                return productive;
+            }
+        }
+    }
+
+    /// The non-terminals reachable from `start` and the terminals used by
+    /// their rules.
+    fn reachable_symbols(
+        &self,
+        start: NonTerminalRef,
+    ) -> (HashSet<NonTerminalRef>, HashSet<TerminalRef>) {
+        let mut reachable = HashSet::from([start]);
+        let mut terminals = HashSet::new();
+        let mut stack = vec![start];
+
+        while let Some(nt_ref) = stack.pop() {
+            for rule in self.rules.iter().filter(|rule| rule.lhs == nt_ref) {
+                for symbol in rule.rhs.iter() {
+                    match symbol {
+                        SymbolRef::NonTerminal(next) => {
+                            if reachable.insert(*next) {
+                                stack.push(*next);
+                            }
+                        }
+                        SymbolRef::Terminal(t_ref) => {
+                            terminals.insert(*t_ref);
+                        }
+                        SymbolRef::Epsilon => {}
+                    }
+                }
+            }
+        }
+
+        (reachable, terminals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grammar::{
+        Rule, Word,
+        symbols::non_terminal::{NonTerminal, NonTerminalKind},
+    };
+
+    fn validate(source: &str) -> Vec<Diagnostic> {
+        source.parse::<Grammar>().unwrap().validate()
+    }
+
+    fn unproductive(name: &str) -> Diagnostic {
+        Diagnostic::Unproductive {
+            non_terminal: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn accepts_clean_grammar() {
+        assert_eq!(validate("terminal a = 'a'; S = a S => { () } | ε;"), []);
+    }
+
+    #[test]
+    fn reports_missing_start() {
+        assert_eq!(Grammar::new().validate(), [Diagnostic::MissingStart]);
+    }
+
+    #[test]
+    fn reports_non_terminal_without_rules() {
+        let mut grammar = Grammar::new();
+        let start = grammar
+            .add_non_terminal(NonTerminal::new("S".to_string(), "()".to_string()))
+            .unwrap();
+        grammar.set_start(start).unwrap();
+
+        assert_eq!(
+            grammar.validate(),
+            [Diagnostic::NoRules {
+                non_terminal: "S".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_unproductive_non_terminals() {
+        assert_eq!(
+            validate(
+                "terminal a = 'a';
+                 S = a T => { () };
+                 T = a T => { () };"
+            ),
+            [unproductive("S"), unproductive("T")]
+        );
+    }
+
+    #[test]
+    fn reports_unreachable_non_terminal() {
+        assert_eq!(
+            validate("terminal a = 'a'; S = a; T = a;"),
+            [Diagnostic::Unreachable {
+                non_terminal: "T".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_unused_terminal() {
+        assert_eq!(
+            validate("terminal a = 'a'; terminal b = 'b'; S = a;"),
+            [Diagnostic::UnusedTerminal {
+                terminal: "b".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_errors_before_warnings() {
+        let diagnostics = validate(
+            "terminal a = 'a'; terminal b = 'b';
+             S = a;
+             T = T a => { () };",
+        );
+
+        assert_eq!(
+            diagnostics,
+            [
+                unproductive("T"),
+                Diagnostic::Unreachable {
+                    non_terminal: "T".to_string()
+                },
+                Diagnostic::UnusedTerminal {
+                    terminal: "b".to_string()
+                },
+            ]
+        );
+        assert!(diagnostics.is_sorted_by_key(|d| d.severity()));
+    }
+
+    #[test]
+    fn stops_after_invalid_rules() {
+        let mut grammar = Grammar::new();
+        let start = grammar
+            .add_non_terminal(NonTerminal::new("S".to_string(), "()".to_string()))
+            .unwrap();
+        // Bypasses `add_rule`, which would reject the dangling terminal
+        grammar.rules.push(Rule::new(
+            start,
+            Word::new(vec![SymbolRef::Terminal(TerminalRef::new(3))]),
+            String::new(),
+        ));
+
+        // The missing start symbol is only reported once the rules are valid
+        assert_eq!(grammar.validate(), [Diagnostic::InvalidRule { rule: 0 }]);
+    }
+
+    #[test]
+    fn reports_invalid_symbols_outside_rules() {
+        let mut grammar = Grammar::new();
+        let list = grammar
+            .add_non_terminal(NonTerminal::new("L".to_string(), "()".to_string()))
+            .unwrap();
+        grammar.non_terminals[list.index()].kind = NonTerminalKind::List {
+            item: SymbolRef::NonTerminal(list),
+            separator: SymbolRef::Terminal(TerminalRef::new(0)),
+            trailing: Default::default(),
+        };
+
+        // Stops before reporting that there are no rules or start symbol
+        assert_eq!(
+            grammar.validate(),
+            [Diagnostic::InvalidNonTerminal {
+                non_terminal: "L".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_terminal_starting_with_skipped_byte() {
+        assert_eq!(
+            validate(
+                "skip [ \t];
+                 terminal a = 'a'; terminal tab = [\t-\n];
+                 S = a tab => { () };"
+            ),
+            [Diagnostic::SkippedStart {
+                terminal: "tab".to_string(),
+                byte: b'\t',
+            }]
+        );
+    }
+}
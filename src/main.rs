@@ -1,114 +1,224 @@
-use grammar::{
-    Grammar, Rule, Word,
-    generator::CodeGenerator,
-    symbols::{
-        non_terminal::NonTerminal,
-        refs::SymbolRef,
-        terminal::{ByteTerminal, TrieTerminal},
-    },
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    process::ExitCode,
 };
-use test::parse;
 
-pub mod grammar;
+use parser_generator::grammar::{
+    Grammar, StrRepr,
+    analysis::{Analysis, Lookahead},
+    generator::{CodeFile, CodeGenerator},
+    loader::LoadError,
+};
 
-pub mod test;
-pub mod test2;
+const USAGE: &str = "\
+Usage: pgen <command> [options]
 
-fn main() {
-    // test_parse();
-    // generate_parser();
-    test_parse_2();
-    // generate_parser_2();
-}
+Commands:
+  generate <grammar> [-o <file>]  Generate a parser module from a grammar file.
+                                  Writes to stdout if no output file is given.
+  check <grammar>                 Check that a parser can be generated.
+  analyze <grammar>               Print the FIRST, FOLLOW and PREDICT sets.
+  help                            Print this message.
 
-fn test_parse() {
-    let input = "aa";
-    let mut input_iter = input.bytes().peekable();
+Exit codes:
+  0  success
+  1  the grammar is invalid
+  2  usage or I/O error";
+
+/// Errors that end the program, mapped to distinct exit codes.
+enum CliError {
+    Usage(String),
+    Io(String),
+    Grammar(String),
+}
 
-    match parse(&mut input_iter) {
-        Ok(_) => println!("Parsing succeeded"),
-        Err(err) => eprintln!("Parsing failed: {}", err),
+impl CliError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            CliError::Grammar(_) => ExitCode::from(1),
+            CliError::Usage(_) | CliError::Io(_) => ExitCode::from(2),
+        }
     }
 }
 
-fn generate_parser() {
-    // Example usage
-    let mut grammar = Grammar::new();
-    let a = grammar.add_terminal(ByteTerminal::from_char('a'));
-    let b = grammar.add_terminal(ByteTerminal::from_char('b'));
-    let s = grammar.add_non_terminal(NonTerminal::new(
-        "S".to_string(),
-        "(char, char)".to_string(),
-    ));
-    grammar.add_rule(Rule::new(
-        s,
-        Word::new(vec![SymbolRef::Terminal(a), SymbolRef::Terminal(b)]),
-        "(res1, res2)".to_string(),
-    ));
-    grammar.add_rule(Rule::new(
-        s,
-        Word::new(vec![SymbolRef::Terminal(b), SymbolRef::Terminal(a)]),
-        "(res1, res2)".to_string(),
-    ));
-    grammar.set_start(s);
+enum Command {
+    Generate {
+        grammar: String,
+        output: Option<String>,
+    },
+    Check {
+        grammar: String,
+    },
+    Analyze {
+        grammar: String,
+    },
+    Help,
+}
 
-    let generator = CodeGenerator::new(grammar);
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    match generator.generate() {
-        Ok(file) => {
-            file.write_to_file("src/test.rs").unwrap();
-            println!("Parser generated successfully!");
+    let result = parse_args(&args).and_then(|command| match command {
+        Command::Generate { grammar, output } => generate(&grammar, output.as_deref()),
+        Command::Check { grammar } => check(&grammar),
+        Command::Analyze { grammar } => analyze(&grammar),
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            match &err {
+                CliError::Usage(message) => eprintln!("error: {}\n\n{}", message, USAGE),
+                CliError::Io(message) | CliError::Grammar(message) => {
+                    eprintln!("error: {}", message)
+                }
+            }
+            err.exit_code()
         }
     }
 }
 
-fn test_parse_2() {
-    let input = "foof";
-    let mut input_iter = input.bytes().peekable();
+fn parse_args(args: &[String]) -> Result<Command, CliError> {
+    let Some((command, rest)) = args.split_first() else {
+        return Err(CliError::Usage("missing command".to_string()));
+    };
+
+    let mut grammar = None;
+    let mut output = None;
+    let mut rest = rest.iter();
+
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-o" | "--output" if command == "generate" => match rest.next() {
+                Some(path) => output = Some(path.clone()),
+                None => return Err(CliError::Usage(format!("`{}` expects a file", arg))),
+            },
+            "-h" | "--help" => return Ok(Command::Help),
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(CliError::Usage(format!("unknown option `{}`", arg)));
+            }
+            _ if grammar.is_none() => grammar = Some(arg.clone()),
+            _ => return Err(CliError::Usage(format!("unexpected argument `{}`", arg))),
+        }
+    }
+
+    let grammar = || grammar.ok_or_else(|| CliError::Usage("missing grammar file".to_string()));
 
-    match test2::parse(&mut input_iter) {
-        Ok(_) => println!("Parsing succeeded"),
-        Err(err) => eprintln!("Parsing failed: {}", err),
+    match command.as_str() {
+        "generate" => Ok(Command::Generate {
+            grammar: grammar()?,
+            output: output.filter(|path| path != "-"),
+        }),
+        "check" => Ok(Command::Check {
+            grammar: grammar()?,
+        }),
+        "analyze" => Ok(Command::Analyze {
+            grammar: grammar()?,
+        }),
+        "help" | "-h" | "--help" => Ok(Command::Help),
+        _ => Err(CliError::Usage(format!("unknown command `{}`", command))),
     }
 }
 
-fn generate_parser_2() {
-    // Example usage
-    let mut grammar = Grammar::new();
-
-    let mut trie = TrieTerminal::new("a".to_string(), "String".to_string());
-    trie.add_word("food".as_bytes(), "\"food\".to_string()".to_string())
-        .unwrap();
-    trie.add_word("foot".as_bytes(), "\"foot\".to_string()".to_string())
-        .unwrap();
-    trie.add_word("for".as_bytes(), "\"for\".to_string()".to_string())
-        .unwrap();
-    trie.add_word("bar".as_bytes(), "\"bar\".to_string()".to_string())
-        .unwrap();
-    trie.add_word("baz".as_bytes(), "\"baz\".to_string()".to_string())
-        .unwrap();
-    let a = grammar.add_terminal(trie);
-
-    let s = grammar.add_non_terminal(NonTerminal::new("S".to_string(), "String".to_string()));
-    grammar.add_rule(Rule::new(
-        s,
-        Word::new(vec![SymbolRef::Terminal(a)]),
-        "res1".to_string(),
-    ));
-    grammar.set_start(s);
+fn load(path: &str) -> Result<Grammar, CliError> {
+    Grammar::load(path).map_err(|err| match err {
+        LoadError::Io { .. } => CliError::Io(err.to_string()),
+        LoadError::Syntax { .. } => CliError::Grammar(err.to_string()),
+    })
+}
+
+fn compile(path: &str) -> Result<CodeFile, CliError> {
+    let grammar = load(path)?;
+
+    // Errors are part of the generation error, only warnings need reporting
+    for diagnostic in grammar.validate().iter().filter(|d| !d.is_error()) {
+        eprintln!("{}: {}: {}", diagnostic.severity(), path, diagnostic);
+    }
 
     let generator = CodeGenerator::new(grammar);
 
-    match generator.generate() {
-        Ok(file) => {
-            file.write_to_file("src/test2.rs").unwrap();
-            println!("Parser generated successfully!");
-        }
-        Err(e) => {
-            eprintln!("Error: {}", e);
+    generator
+        .generate()
+        .map_err(|err| CliError::Grammar(format!("{}: {}", path, err)))
+}
+
+fn generate(path: &str, output: Option<&str>) -> Result<(), CliError> {
+    let file = compile(path)?;
+
+    match output {
+        Some(output) => file
+            .write_to_file(output)
+            .map_err(|err| CliError::Io(format!("{}: {}", output, err))),
+        None => writeln!(io::stdout(), "{}", file).map_err(|err| CliError::Io(err.to_string())),
+    }
+}
+
+fn check(path: &str) -> Result<(), CliError> {
+    compile(path)?;
+    eprintln!("{}: ok", path);
+    Ok(())
+}
+
+fn analyze(path: &str) -> Result<(), CliError> {
+    let grammar = load(path)?;
+
+    println!("{}", grammar.repr());
+    println!();
+
+    let analysis = Analysis::new(&grammar);
+    for (nt, nt_ref) in grammar.iter_non_terminals() {
+        println!("{}", nt.repr(&grammar));
+        println!("  nullable: {}", analysis.is_nullable(nt_ref));
+        println!(
+            "  first:    {}",
+            format_lookaheads(analysis.first(nt_ref), &grammar)
+        );
+        println!(
+            "  follow:   {}",
+            format_lookaheads(analysis.follow(nt_ref), &grammar)
+        );
+        for (i, rule) in grammar.iter_rules_for_non_terminal(nt_ref) {
+            println!(
+                "  predict:  {}  for  {}",
+                format_lookaheads(analysis.predict(i), &grammar),
+                rule.repr(&grammar)
+            );
         }
     }
+
+    let cycles = analysis.left_recursion(&grammar);
+    let conflicts = analysis.all_conflicts(&grammar);
+    println!();
+    if cycles.is_empty() && conflicts.is_empty() {
+        println!("The grammar is LL(1).");
+    }
+    for cycle in cycles {
+        println!("{}", cycle);
+    }
+    for conflict in conflicts {
+        println!("{}", conflict);
+    }
+
+    Ok(())
+}
+
+/// Renders a set of lookaheads in sorted order, printable bytes as
+/// characters and tokens by their terminal's name.
+fn format_lookaheads(lookaheads: &HashSet<Lookahead>, grammar: &Grammar) -> String {
+    let mut lookaheads: Vec<_> = lookaheads.iter().collect();
+    lookaheads.sort();
+
+    format!(
+        "{{{}}}",
+        lookaheads
+            .iter()
+            .map(|lookahead| lookahead.repr(grammar))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
 }
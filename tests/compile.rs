@@ -0,0 +1,40 @@
+use std::fs;
+
+use parser_generator::build::{BuildError, compile_to};
+
+fn out_dir(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("compile-test-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn writes_parser_named_after_grammar() {
+    let out_dir = out_dir("ab");
+    let output = compile_to("grammars/ab.grammar", &out_dir).unwrap();
+
+    // `include_parser!("ab")` includes this file
+    assert_eq!(output, out_dir.join("ab.rs"));
+    let source = fs::read_to_string(&output).unwrap();
+    assert!(source.contains("pub fn parse("));
+
+    fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn reports_missing_grammar() {
+    let result = compile_to("grammars/missing.grammar", out_dir("missing"));
+    assert!(matches!(result, Err(BuildError::Load(_))));
+}
+
+#[test]
+fn reports_invalid_grammar_without_writing() {
+    let out_dir = out_dir("invalid");
+    fs::create_dir_all(&out_dir).unwrap();
+    let grammar = out_dir.join("conflict.grammar");
+    fs::write(&grammar, "terminal a = 'a';\nS = a | a a => { () };\n").unwrap();
+
+    let result = compile_to(&grammar, &out_dir);
+    assert!(matches!(result, Err(BuildError::Generate { .. })));
+    assert!(!out_dir.join("conflict.rs").exists());
+
+    fs::remove_dir_all(&out_dir).unwrap();
+}
@@ -0,0 +1,84 @@
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+fn pgen(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pgen"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Writes a grammar into a file of its own in the temporary directory.
+fn grammar_file(name: &str, source: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pgen-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, source).unwrap();
+    path
+}
+
+#[test]
+fn check_accepts_valid_grammar() {
+    let output = pgen(&["check", "grammars/ab.grammar"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "grammars/ab.grammar: ok\n"
+    );
+}
+
+#[test]
+fn generate_writes_parser_to_stdout() {
+    let output = pgen(&["generate", "grammars/ab.grammar"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("pub fn parse("));
+}
+
+#[test]
+fn syntax_error_exits_with_1() {
+    let path = grammar_file("syntax.grammar", "terminal a = 'a';\nS = a b;\n");
+    let output = pgen(&["check", path.to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("error: {}:2:7: unknown symbol `b`\n", path.display())
+    );
+}
+
+#[test]
+fn conflict_exits_with_1() {
+    let path = grammar_file(
+        "conflict.grammar",
+        "terminal a = 'a';\nS = a | a a => { () };\n",
+    );
+    let output = pgen(&["check", path.to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with(&format!("error: {}: ", path.display())));
+    assert!(stderr.contains("not LL(1)"), "{}", stderr);
+}
+
+#[test]
+fn unreadable_path_exits_with_2() {
+    let output = pgen(&["check", "does/not/exist.grammar"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: does/not/exist.grammar: "));
+}
+
+#[test]
+fn usage_error_exits_with_2() {
+    let output = pgen(&["frobnicate"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).starts_with("error: unknown command `frobnicate`")
+    );
+}